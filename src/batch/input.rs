@@ -0,0 +1,111 @@
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::Path;
+use crate::error::{ConversionError, ConversionResult};
+
+/// A single query of a batch
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchEntry {
+    /// Identifier reported with the results (defaults to the line or list position)
+    #[serde(default, deserialize_with = "deserialize_id")]
+    pub id: String,
+    /// Query statement
+    pub query: String,
+    /// Source platform of the query (defaults to the batch source platform)
+    #[serde(default, alias = "source")]
+    pub platform: Option<String>,
+}
+
+/// Identifier as written in a JSON or YAML batch file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EntryId {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+}
+
+/// Read an identifier written as a string or a number
+fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match EntryId::deserialize(deserializer)? {
+        EntryId::Text(id) => id,
+        EntryId::Integer(id) => id.to_string(),
+        EntryId::Float(id) => id.to_string(),
+    })
+}
+
+/// Loader for batch query files
+pub struct BatchInput;
+
+impl BatchInput {
+    /// Load batch entries, choosing the format from the file extension
+    ///
+    /// `.json`, `.yaml` and `.yml` files hold a list of entries; any other file
+    /// holds one query per line, skipping blank lines and `#` comments.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> ConversionResult<Vec<BatchEntry>> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| {
+            ConversionError::IoError(format!("Failed to read batch file '{}': {}", path.display(), e))
+        })?;
+
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        let mut entries: Vec<BatchEntry> = match extension.as_deref() {
+            Some("json") => serde_json::from_str(&content).map_err(|e| {
+                ConversionError::SerializationError(format!("Failed to parse batch file '{}': {}", path.display(), e))
+            })?,
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&content).map_err(|e| {
+                ConversionError::SerializationError(format!("Failed to parse batch file '{}': {}", path.display(), e))
+            })?,
+            _ => return Ok(Self::parse_lines(&content)),
+        };
+
+        Self::assign_default_ids(&mut entries);
+        Ok(entries)
+    }
+
+    /// Parse one query per line, using line numbers as identifiers
+    pub fn parse_lines(content: &str) -> Vec<BatchEntry> {
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| {
+                let line = line.trim();
+                !line.is_empty() && !line.starts_with('#')
+            })
+            .map(|(index, line)| BatchEntry {
+                id: (index + 1).to_string(),
+                query: line.trim().to_string(),
+                platform: None,
+            })
+            .collect()
+    }
+
+    /// Number list entries without an explicit identifier by position
+    fn assign_default_ids(entries: &mut [BatchEntry]) {
+        for (index, entry) in entries.iter_mut().enumerate() {
+            if entry.id.is_empty() {
+                entry.id = (index + 1).to_string();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_ids_are_read_as_text() {
+        let json = r#"[{"id": 1, "query": "a"}, {"id": "two", "query": "b"}, {"query": "c"}]"#;
+        let entries: Vec<BatchEntry> = serde_json::from_str(json).unwrap();
+        let ids: Vec<&str> = entries.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, ["1", "two", ""]);
+
+        let entries: Vec<BatchEntry> = serde_yaml::from_str("- id: 7\n  query: a\n").unwrap();
+        assert_eq!(entries[0].id, "7");
+    }
+}
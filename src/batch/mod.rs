@@ -0,0 +1,5 @@
+pub mod input;
+pub mod processor;
+
+pub use input::*;
+pub use processor::*;
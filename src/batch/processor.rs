@@ -0,0 +1,146 @@
+use serde::Serialize;
+use crate::batch::BatchEntry;
+use crate::converter::{PlatformConversion, QueryConverter};
+use crate::error::ConversionError;
+
+/// Conversion result of a single batch entry
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchRecord {
+    pub id: String,
+    /// Platform of the query, `None` when neither the entry nor the batch gives one
+    pub source_platform: Option<String>,
+    pub original_query: String,
+    /// Lint warnings about the original query; warnings of a conversion are on the conversion
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Converted query per target platform, as in the `conversions` of a `ConversionReport`
+    pub conversions: Vec<PlatformConversion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Stable code of the error, e.g. `CX0102`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+}
+
+impl BatchRecord {
+    /// Check whether the entry converted successfully
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Converts batches of queries, continuing past individual failures
+pub struct BatchProcessor<'a> {
+    converter: &'a QueryConverter,
+    default_platform: Option<String>,
+    target_platforms: Vec<String>,
+}
+
+impl<'a> BatchProcessor<'a> {
+    /// Create a batch processor
+    ///
+    /// Entries without their own platform use `default_platform`; an empty
+    /// `target_platforms` converts to every platform except the source.
+    pub fn new(
+        converter: &'a QueryConverter,
+        default_platform: Option<String>,
+        target_platforms: Vec<String>,
+    ) -> Self {
+        Self {
+            converter,
+            default_platform,
+            target_platforms,
+        }
+    }
+
+    /// Convert every entry of the batch
+    pub fn process(&self, entries: &[BatchEntry]) -> Vec<BatchRecord> {
+        entries.iter().map(|entry| self.process_entry(entry)).collect()
+    }
+
+    /// Convert a single entry, recording any failure in the result
+    fn process_entry(&self, entry: &BatchEntry) -> BatchRecord {
+        let source_platform = entry
+            .platform
+            .clone()
+            .or_else(|| self.default_platform.clone())
+            .filter(|platform| !platform.is_empty());
+
+        let mut record = BatchRecord {
+            id: entry.id.clone(),
+            source_platform: source_platform.clone(),
+            original_query: entry.query.clone(),
+            warnings: Vec::new(),
+            conversions: Vec::new(),
+            error: None,
+            error_code: None,
+        };
+
+        let Some(source_platform) = source_platform else {
+            let error = ConversionError::MissingPlatform;
+            record.error = Some(error.to_string());
+            record.error_code = Some(error.code().code.to_string());
+            return record;
+        };
+
+        let target_platforms = if self.target_platforms.is_empty() {
+            self.converter.get_other_platforms(&source_platform)
+        } else {
+            self.target_platforms
+                .iter()
+                .filter(|platform| *platform != &source_platform)
+                .cloned()
+                .collect()
+        };
+
+        match self.converter.convert_all(&entry.query, &source_platform, &target_platforms) {
+            Ok(report) => {
+                record.warnings = report.warnings;
+                record.conversions = report.conversions;
+            }
+            Err(e) => {
+                record.error = Some(e.to_string());
+                record.error_code = Some(e.code().code.to_string());
+            }
+        }
+
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigManager, EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+
+    fn entry(query: &str, platform: Option<&str>) -> BatchEntry {
+        BatchEntry {
+            id: "1".to_string(),
+            query: query.to_string(),
+            platform: platform.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn entries_without_a_platform_fail_with_a_code() {
+        let converter = QueryConverter::new(ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap());
+        let processor = BatchProcessor::new(&converter, None, vec!["fofa".to_string()]);
+        let records = processor.process(&[entry(r#"title:"a""#, None), entry(r#"title:"a""#, Some("quake"))]);
+
+        let json = serde_json::to_value(&records[0]).unwrap();
+        assert_eq!(json["source_platform"], serde_json::Value::Null);
+        assert_eq!(json["error_code"], "CX0603");
+        assert!(records[1].is_success());
+        assert_eq!(records[1].source_platform.as_deref(), Some("quake"));
+        assert_eq!(records[1].conversions[0].query, r#"title="a""#);
+    }
+
+    #[test]
+    fn failed_entries_carry_the_code_of_their_error() {
+        let converter = QueryConverter::new(ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap());
+        let processor = BatchProcessor::new(&converter, Some("fofa".to_string()), Vec::new());
+        let records = processor.process(&[entry(r#"nope="a""#, None)]);
+        assert_eq!(records[0].error_code.as_deref(), Some("CX0102"));
+        assert!(records[0].conversions.is_empty());
+    }
+}
@@ -1,7 +1,9 @@
-use clap::{Parser, ValueEnum};
+use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
 use std::fs;
+use std::io::{self, IsTerminal, Read};
 use std::path::PathBuf;
-use std::process;
+use crate::config::{MissingFieldPolicy, CANONICAL_PLATFORM};
+use crate::error::{ConversionError, ConversionResult};
 
 /// Command line arguments
 #[derive(Parser)]
@@ -9,28 +11,351 @@ use std::process;
 #[command(author = "key")]
 #[command(about = "[Cyberspace Asset Mapping Platform Query Statement Conversion Tool]")]
 #[command(version = "0.1.0")]
+#[command(after_help = "Exit codes:
+  0  Success
+  1  Internal error
+  2  Usage error (invalid arguments, no query, source platform or known error code given)
+  3  Configuration error
+  4  Query rejected (unsupported platform, field, operator, invalid syntax, untranslatable field or too many conditions to compare)
+  5  I/O error (reading input or writing output failed)
+  6  Serialization error
+  7  Batch conversion finished with failed queries
+  8  A check failed: fmt --check found unformatted queries or equiv found a difference")]
+#[command(subcommand_negates_reqs = true)]
 pub struct Args {
-    /// Configuration file path
-    #[arg(short = 'c', long = "config", default_value = "config.json")]
-    pub config: PathBuf,
+    /// Configuration file (JSON, TOML or YAML) overriding or extending the built-in mappings;
+    /// repeat to layer several files, later ones taking precedence
+    /// (defaults to $CONVERTIX_CONFIG, then convertix/config.{json,toml,yaml} in the XDG config directories)
+    #[arg(short = 'c', long = "config", global = true)]
+    pub config: Vec<PathBuf>,
 
-    /// Query statement (use @filename to read from file)
+    /// Subcommand to run
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Conversion options used when no subcommand is given (alias for `convert`)
+    #[command(flatten)]
+    pub convert: Option<ConvertArgs>,
+}
+
+/// Available subcommands
+#[derive(Subcommand)]
+pub enum Command {
+    /// Convert a query statement to other platforms
+    Convert(ConvertArgs),
+    /// Convert a file of queries, one per line or as a JSON/YAML list
+    Batch(BatchArgs),
+    /// Validate query syntax for a platform
+    Validate(QueryArgs),
+    /// Detect which platforms accept a query statement
+    Detect(DetectArgs),
+    /// List supported platforms
+    Platforms,
+    /// Show the field mapping matrix of the configured platforms
+    Fields(FieldsArgs),
+    /// Explain how the fields of a query map to other platforms
+    Explain(QueryArgs),
+    /// Simplify a query and print it in canonical spacing
+    Fmt(FmtArgs),
+    /// Check whether two queries, possibly of different platforms, match the same records
+    Equiv(EquivArgs),
+    /// Start an interactive session converting queries as they are entered
+    Repl(ReplArgs),
+    /// Explain an error code such as CX0102, or list all codes
+    ExplainError(ExplainErrorArgs),
+    /// Configuration file utilities
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Generate a shell completion script
+    Completions(CompletionsArgs),
+    /// Print completion candidates for the shell completion scripts
+    #[command(name = "__complete", hide = true)]
+    Complete(CompleteArgs),
+}
+
+/// Arguments of the `convert` command
+#[derive(ClapArgs)]
+pub struct ConvertArgs {
+    /// Query statement (use @filename to read from file, - or omit to read from stdin)
     #[arg(short = 'q', long = "query")]
-    pub query: String,
+    pub query: Option<String>,
 
-    /// Source platform of the query statement
+    /// Source platform of the query statement (convertix for the canonical syntax)
     #[arg(short = 'p', long = "platform")]
     pub platform: String,
 
+    /// Target platforms, comma separated (defaults to all other configured platforms; convertix renders the canonical syntax)
+    #[arg(short = 't', long = "to", value_delimiter = ',')]
+    pub to: Vec<String>,
+
     /// Output format
     #[arg(short = 'f', long = "format", default_value = "raw")]
     pub format: OutputFormat,
 
+    /// Print only the converted query, or `platform<TAB>query` lines for several targets
+    #[arg(long = "plain", conflicts_with = "format")]
+    pub plain: bool,
+
+    /// What to do with fields that have no equivalent on a target platform and set no
+    /// 'on_missing' policy of their own (an 'approximations' entry for the target takes precedence)
+    #[arg(long = "on-missing", default_value = "keep")]
+    pub on_missing: OnMissing,
+
+    /// Output file path (optional, defaults to stdout)
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<PathBuf>,
+}
+
+impl ConvertArgs {
+    /// Get the effective output format, taking `--plain` into account
+    pub fn output_format(&self) -> OutputFormat {
+        if self.plain {
+            OutputFormat::Plain
+        } else {
+            self.format.clone()
+        }
+    }
+}
+
+/// Arguments of the `batch` command
+#[derive(ClapArgs)]
+pub struct BatchArgs {
+    /// Batch file: one query per line, or a .json/.yaml/.yml list of {id, query, platform}
+    #[arg(short = 'i', long = "input")]
+    pub input: PathBuf,
+
+    /// Source platform for queries that do not name their own (convertix for the canonical syntax)
+    #[arg(short = 'p', long = "platform")]
+    pub platform: Option<String>,
+
+    /// Target platforms, comma separated (defaults to all other configured platforms; convertix renders the canonical syntax)
+    #[arg(short = 't', long = "to", value_delimiter = ',')]
+    pub to: Vec<String>,
+
+    /// Batch output format
+    #[arg(short = 'f', long = "format", default_value = "jsonl")]
+    pub format: BatchFormat,
+
+    /// What to do with fields that have no equivalent on a target platform and set no
+    /// 'on_missing' policy of their own (an 'approximations' entry for the target takes precedence)
+    #[arg(long = "on-missing", default_value = "keep")]
+    pub on_missing: OnMissing,
+
+    /// Output file path (optional, defaults to stdout)
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<PathBuf>,
+}
+
+/// Arguments of commands operating on a single query
+#[derive(ClapArgs)]
+pub struct QueryArgs {
+    /// Query statement (use @filename to read from file, - or omit to read from stdin)
+    #[arg(short = 'q', long = "query")]
+    pub query: Option<String>,
+
+    /// Platform of the query statement (convertix for the canonical syntax)
+    #[arg(short = 'p', long = "platform")]
+    pub platform: String,
+}
+
+/// Arguments of the `fmt` command
+#[derive(ClapArgs)]
+pub struct FmtArgs {
+    /// Query statement (use @filename to read from file, - or omit to read from stdin)
+    #[arg(short = 'q', long = "query", conflicts_with = "files")]
+    pub query: Option<String>,
+
+    /// Platform of the queries (convertix for the canonical syntax)
+    #[arg(short = 'p', long = "platform")]
+    pub platform: String,
+
+    /// Query files to format, one query per line, or separated by blank lines with -m; blank lines and # comments are kept
+    pub files: Vec<PathBuf>,
+
+    /// Lay out nested groups over several indented lines
+    #[arg(short = 'm', long = "multiline")]
+    pub multiline: bool,
+
+    /// Print nothing and fail if the query or a file is not formatted
+    #[arg(long = "check")]
+    pub check: bool,
+
+    /// Rewrite the files in place
+    #[arg(short = 'w', long = "write", requires = "files", conflicts_with = "check")]
+    pub write: bool,
+}
+
+/// Arguments of the `equiv` command
+#[derive(ClapArgs)]
+pub struct EquivArgs {
+    /// First query statement (use @filename to read from file, - to read from stdin)
+    #[arg(short = 'a', long = "query-a")]
+    pub query_a: String,
+
+    /// Platform of the first query (convertix for the canonical syntax)
+    #[arg(long = "pa", value_name = "PLATFORM")]
+    pub platform_a: String,
+
+    /// Second query statement (use @filename to read from file, - to read from stdin)
+    #[arg(short = 'b', long = "query-b")]
+    pub query_b: String,
+
+    /// Platform of the second query (convertix for the canonical syntax)
+    #[arg(long = "pb", value_name = "PLATFORM")]
+    pub platform_b: String,
+}
+
+/// Arguments of the `detect` command
+#[derive(ClapArgs)]
+pub struct DetectArgs {
+    /// Query statement (use @filename to read from file, - or omit to read from stdin)
+    #[arg(short = 'q', long = "query")]
+    pub query: Option<String>,
+}
+
+/// Arguments of the `fields` command
+#[derive(ClapArgs)]
+pub struct FieldsArgs {
+    /// Only include these canonical fields (defaults to all fields)
+    pub fields: Vec<String>,
+
+    /// Only include these platforms, comma separated (defaults to all platforms)
+    #[arg(short = 'p', long = "platform", value_delimiter = ',')]
+    pub platforms: Vec<String>,
+
+    /// Describe each field: native name, type, match modes, aliases, examples and VIP status
+    #[arg(short = 'd', long = "describe")]
+    pub describe: bool,
+
+    /// Matrix format
+    #[arg(short = 'f', long = "format", default_value = "table")]
+    pub format: MatrixFormat,
+
     /// Output file path (optional, defaults to stdout)
     #[arg(short = 'o', long = "output")]
     pub output: Option<PathBuf>,
 }
 
+/// Arguments of the `repl` command
+#[derive(ClapArgs)]
+pub struct ReplArgs {
+    /// Initial source platform (defaults to the first configured platform)
+    #[arg(short = 'p', long = "platform")]
+    pub platform: Option<String>,
+
+    /// Initial target platforms, comma separated (defaults to all other platforms)
+    #[arg(short = 't', long = "to", value_delimiter = ',')]
+    pub to: Vec<String>,
+
+    /// History file (defaults to $CONVERTIX_HISTORY or ~/.convertix_history)
+    #[arg(long = "history")]
+    pub history: Option<PathBuf>,
+
+    /// What to do with fields that have no equivalent on a target platform and set no
+    /// 'on_missing' policy of their own (an 'approximations' entry for the target takes precedence)
+    #[arg(long = "on-missing", default_value = "keep")]
+    pub on_missing: OnMissing,
+}
+
+/// Arguments of the `explain-error` command
+#[derive(ClapArgs)]
+pub struct ExplainErrorArgs {
+    /// Error code or name to explain (lists all codes when omitted)
+    pub code: Option<String>,
+}
+
+/// Arguments of the `completions` command
+#[derive(ClapArgs)]
+pub struct CompletionsArgs {
+    /// Shell to generate the completion script for
+    pub shell: CompletionShell,
+}
+
+/// Arguments of the hidden `__complete` command
+#[derive(ClapArgs)]
+pub struct CompleteArgs {
+    /// Kind of value to complete
+    pub kind: CompletionKind,
+
+    /// Platform whose field names are completed
+    #[arg(short = 'p', long = "platform", default_value = CANONICAL_PLATFORM)]
+    pub platform: String,
+}
+
+/// Shells supported by the `completions` command
+#[derive(Clone, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Values offered by the hidden `__complete` command
+#[derive(Clone, ValueEnum)]
+pub enum CompletionKind {
+    /// Platform names from the configuration
+    Platforms,
+    /// Native field names and aliases of the `--platform`
+    Fields,
+}
+
+/// Configuration subcommands
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Check the configuration for errors and likely mistakes
+    Check(ConfigCheckArgs),
+    /// Show the configuration layers, or the merged configuration
+    Show(ConfigShowArgs),
+    /// Print the JSON Schema of the configuration file format
+    Schema,
+    /// Normalize a configuration file: sort platforms and fields, keep comments
+    Fmt(ConfigFmtArgs),
+}
+
+/// Arguments for `config fmt`
+#[derive(ClapArgs)]
+pub struct ConfigFmtArgs {
+    /// Configuration file to format (JSON, TOML or YAML by extension)
+    pub file: PathBuf,
+
+    /// Rewrite the file in place instead of printing the result
+    #[arg(short = 'w', long = "write")]
+    pub write: bool,
+}
+
+/// Arguments for `config check`
+#[derive(ClapArgs)]
+pub struct ConfigCheckArgs {
+    /// Fail on warnings as well as errors
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Output format
+    #[arg(short = 'f', long = "format", value_enum, default_value = "text")]
+    pub format: ConfigOutputFormat,
+}
+
+/// Arguments for `config show`
+#[derive(ClapArgs)]
+pub struct ConfigShowArgs {
+    /// Print the effective merged configuration and the source of each value
+    #[arg(long)]
+    pub resolved: bool,
+
+    /// Output format
+    #[arg(short = 'f', long = "format", value_enum, default_value = "text")]
+    pub format: ConfigOutputFormat,
+}
+
+/// Output formats of the `config` commands
+#[derive(Clone, ValueEnum)]
+pub enum ConfigOutputFormat {
+    /// Human-readable lines
+    Text,
+    /// JSON document
+    Json,
+}
+
 /// Output format options
 #[derive(Clone, ValueEnum)]
 pub enum OutputFormat {
@@ -38,24 +363,203 @@ pub enum OutputFormat {
     Raw,
     /// JSON format output
     Json,
+    /// YAML format output
+    Yaml,
+    /// CSV with one row per target platform
+    Csv,
+    /// Markdown table for reports and wikis
+    Markdown,
+    /// Converted queries only, without decoration
+    Plain,
+}
+
+/// Policy for fields without an equivalent on the target platform
+#[derive(Clone, ValueEnum)]
+pub enum OnMissing {
+    /// Leave the clause unchanged and warn
+    Keep,
+    /// Remove the clause and warn
+    Drop,
+    /// Fail the conversion
+    Error,
+}
+
+impl From<OnMissing> for MissingFieldPolicy {
+    fn from(on_missing: OnMissing) -> Self {
+        match on_missing {
+            OnMissing::Keep => MissingFieldPolicy::Keep,
+            OnMissing::Drop => MissingFieldPolicy::Drop,
+            OnMissing::Error => MissingFieldPolicy::Error,
+        }
+    }
+}
+
+/// Batch output format options
+#[derive(Clone, ValueEnum)]
+pub enum BatchFormat {
+    /// One JSON object per query
+    Jsonl,
+    /// CSV with one row per query and target platform
+    Csv,
+}
+
+/// Field matrix format options
+#[derive(Clone, ValueEnum)]
+pub enum MatrixFormat {
+    /// Aligned text table
+    Table,
+    /// Markdown table
+    Markdown,
+    /// CSV with a header row
+    Csv,
+    /// JSON format output
+    Json,
 }
 
 impl Args {
-    /// Parse query input, supporting file input with @ prefix
-    pub fn parse_query_input(&self) -> String {
-        if self.query.starts_with('@') {
-            // Read from file
-            let file_path = &self.query[1..]; // Remove @ prefix
-            match fs::read_to_string(file_path) {
-                Ok(content) => content.trim().to_string(),
-                Err(e) => {
-                    eprintln!("ERROR: Failed to read query file '{}': {}", file_path, e);
-                    process::exit(1);
-                }
+    /// Resolve the command to run, treating top-level conversion flags as `convert`
+    pub fn into_command(self) -> Option<Command> {
+        self.command.or(self.convert.map(Command::Convert))
+    }
+
+    /// Check whether errors should be reported as JSON, following the requested output format
+    pub fn wants_json_errors(&self) -> bool {
+        let command = self.command.as_ref();
+        let convert_args = match command {
+            Some(Command::Convert(convert_args)) => Some(convert_args),
+            Some(_) => None,
+            None => self.convert.as_ref(),
+        };
+
+        match (command, convert_args) {
+            (_, Some(convert_args)) => matches!(convert_args.format, OutputFormat::Json),
+            (Some(Command::Fields(fields_args)), _) => matches!(fields_args.format, MatrixFormat::Json),
+            (Some(Command::Batch(batch_args)), _) => matches!(batch_args.format, BatchFormat::Jsonl),
+            (Some(Command::Config(ConfigCommand::Check(check_args))), _) => {
+                matches!(check_args.format, ConfigOutputFormat::Json)
             }
-        } else {
-            // Use input directly
-            self.query.clone()
+            (Some(Command::Config(ConfigCommand::Show(show_args))), _) => {
+                matches!(show_args.format, ConfigOutputFormat::Json)
+            }
+            _ => false,
+        }
+    }
+
+    /// Parse query input, supporting file input with @ prefix and stdin input with - or no query
+    pub fn parse_query_input(query: Option<&str>) -> ConversionResult<String> {
+        match query {
+            Some("-") => Self::read_query_from_stdin(),
+            Some(query) => match query.strip_prefix('@') {
+                // Read from file
+                Some(file_path) => fs::read_to_string(file_path)
+                    .map(|content| content.trim().to_string())
+                    .map_err(|e| ConversionError::IoError(format!("Failed to read query file '{}': {}", file_path, e))),
+                // Use input directly
+                None => Ok(query.to_string()),
+            },
+            // Only fall back to stdin when it is piped, never block on an interactive terminal
+            None if !io::stdin().is_terminal() => Self::read_query_from_stdin(),
+            None => Err(ConversionError::MissingQuery(
+                "No query given, use -q <QUERY>, -q @filename or pipe the query on stdin".to_string(),
+            )),
+        }
+    }
+
+    /// Read the whole of stdin as the query
+    fn read_query_from_stdin() -> ConversionResult<String> {
+        Self::read_query(io::stdin())
+    }
+
+    /// Read the whole of a reader as the query
+    fn read_query(mut reader: impl Read) -> ConversionResult<String> {
+        let mut content = String::new();
+        reader
+            .read_to_string(&mut content)
+            .map_err(|e| ConversionError::IoError(format!("Failed to read query from stdin: {}", e)))?;
+
+        let query = content.trim();
+        if query.is_empty() {
+            return Err(ConversionError::MissingQuery("No query received on stdin".to_string()));
         }
+        Ok(query.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn parse(args: &[&str]) -> Args {
+        Args::try_parse_from(std::iter::once("convertix").chain(args.iter().copied())).unwrap()
+    }
+
+    #[test]
+    fn command_definition_is_valid() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn top_level_flags_are_the_convert_command() {
+        let Some(Command::Convert(convert_args)) = parse(&["-q", "title=\"a\"", "-p", "fofa", "-t", "quake,hunter"]).into_command() else {
+            panic!("not a convert command");
+        };
+        assert_eq!(convert_args.query.as_deref(), Some("title=\"a\""));
+        assert_eq!(convert_args.platform, "fofa");
+        assert_eq!(convert_args.to, ["quake", "hunter"]);
+
+        let Some(Command::Convert(convert_args)) = parse(&["convert", "-q", "a", "-p", "quake"]).into_command() else {
+            panic!("not a convert command");
+        };
+        assert_eq!(convert_args.platform, "quake");
+        assert!(convert_args.to.is_empty());
+    }
+
+    #[test]
+    fn subcommands_do_not_require_conversion_flags() {
+        assert!(matches!(parse(&["platforms"]).into_command(), Some(Command::Platforms)));
+        assert!(matches!(parse(&["validate", "-q", "a", "-p", "fofa"]).into_command(), Some(Command::Validate(_))));
+        assert!(matches!(parse(&["-c", "a.json", "config", "check"]).into_command(), Some(Command::Config(ConfigCommand::Check(_)))));
+    }
+
+    #[test]
+    fn queries_are_read_from_files_or_given_directly() {
+        assert_eq!(Args::parse_query_input(Some(r#"title="a""#)).unwrap(), r#"title="a""#);
+
+        let path = std::env::temp_dir().join(format!("convertix-query-{}.txt", std::process::id()));
+        fs::write(&path, "  title=\"a\" && port=\"80\"\n\n").unwrap();
+        let query = Args::parse_query_input(Some(&format!("@{}", path.display())));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(query.unwrap(), r#"title="a" && port="80""#);
+
+        assert!(matches!(
+            Args::parse_query_input(Some("@/nonexistent/convertix/query.txt")),
+            Err(ConversionError::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn piped_queries_are_trimmed_and_must_not_be_empty() {
+        assert_eq!(Args::read_query("title=\"a\"\n".as_bytes()).unwrap(), r#"title="a""#);
+        assert!(matches!(Args::read_query(" \n\t".as_bytes()), Err(ConversionError::MissingQuery(_))));
+    }
+
+    #[test]
+    fn errors_are_json_when_the_output_is() {
+        assert!(parse(&["-q", "a", "-p", "fofa", "-f", "json"]).wants_json_errors());
+        assert!(parse(&["convert", "-q", "a", "-p", "fofa", "-f", "json"]).wants_json_errors());
+        assert!(!parse(&["-q", "a", "-p", "fofa", "-f", "yaml"]).wants_json_errors());
+        assert!(parse(&["fields", "-f", "json"]).wants_json_errors());
+        assert!(parse(&["batch", "-i", "q.txt"]).wants_json_errors());
+        assert!(!parse(&["batch", "-i", "q.txt", "-f", "csv"]).wants_json_errors());
+        assert!(parse(&["config", "check", "-f", "json"]).wants_json_errors());
+        assert!(parse(&["config", "show", "-f", "json"]).wants_json_errors());
+        assert!(!parse(&["platforms"]).wants_json_errors());
+    }
+
+    #[test]
+    fn conversion_flags_need_a_platform() {
+        assert!(Args::try_parse_from(["convertix", "-q", "a"]).is_err());
+        assert!(Args::try_parse_from(["convertix", "convert", "-q", "a"]).is_err());
     }
 }
@@ -0,0 +1,549 @@
+use crate::batch::{BatchInput, BatchProcessor};
+use crate::cli::{
+    generate_completions, Args, BatchArgs, CompleteArgs, CompletionKind, CompletionsArgs, Command, ConfigCheckArgs, ConfigCommand, ConfigFmtArgs, ConfigShowArgs, ConvertArgs, DetectArgs, EquivArgs, ExplainErrorArgs, FieldsArgs, FmtArgs, OutputFormat,
+    QueryArgs, Repl, ReplArgs,
+};
+use crate::config::{ConfigFormat, ConfigLinter, ConfigLoader, ConfigManager, FieldCatalog, FieldMatrix, IssueSeverity, CANONICAL_PLATFORM, CONFIG_SCHEMA};
+use crate::converter::{Equivalence, QueryConverter, SyntaxValidator};
+use crate::error::{ConversionError, ConversionResult, ErrorCode, ErrorReport, ERROR_CODES};
+use crate::output::OutputFormatter;
+use clap::CommandFactory;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Run the command selected on the command line
+pub fn run(args: Args) -> ConversionResult<()> {
+    let config_paths = args.config.clone();
+
+    let command = match args.into_command() {
+        Some(command) => command,
+        None => {
+            Args::command()
+                .print_help()
+                .map_err(|e| ConversionError::IoError(format!("Failed to print help: {}", e)))?;
+            return Ok(());
+        }
+    };
+
+    match command {
+        Command::Convert(convert_args) => run_convert(&config_paths, convert_args),
+        Command::Batch(batch_args) => run_batch(&config_paths, batch_args),
+        Command::Validate(query_args) => run_validate(&config_paths, query_args),
+        Command::Detect(detect_args) => run_detect(&config_paths, detect_args),
+        Command::Platforms => run_platforms(&config_paths),
+        Command::Fields(fields_args) => run_fields(&config_paths, fields_args),
+        Command::Explain(query_args) => run_explain(&config_paths, query_args),
+        Command::Fmt(fmt_args) => run_fmt(&config_paths, fmt_args),
+        Command::Equiv(equiv_args) => run_equiv(&config_paths, equiv_args),
+        Command::Repl(repl_args) => run_repl(&config_paths, repl_args),
+        Command::ExplainError(explain_args) => run_explain_error(explain_args),
+        Command::Config(ConfigCommand::Check(check_args)) => run_config_check(&config_paths, check_args),
+        Command::Config(ConfigCommand::Show(show_args)) => run_config_show(&config_paths, show_args),
+        Command::Config(ConfigCommand::Fmt(fmt_args)) => run_config_fmt(fmt_args),
+        Command::Config(ConfigCommand::Schema) => OutputFormatter::write_output(CONFIG_SCHEMA.to_string(), None),
+        Command::Completions(completions_args) => run_completions(completions_args),
+        Command::Complete(complete_args) => run_complete(&config_paths, complete_args),
+    }
+}
+
+/// Load configuration and create a converter
+fn load_converter(config_paths: &[PathBuf]) -> ConversionResult<QueryConverter> {
+    Ok(QueryConverter::new(ConfigLoader::load(config_paths)?))
+}
+
+/// Ensure a platform is present in the configuration
+fn require_platform(converter: &QueryConverter, platform: &str) -> ConversionResult<()> {
+    if converter.is_platform_supported(platform) {
+        Ok(())
+    } else {
+        Err(ConversionError::UnsupportedPlatform(platform.to_string()))
+    }
+}
+
+/// Resolve requested target platforms, defaulting to every platform except the source
+fn resolve_targets(
+    converter: &QueryConverter,
+    source_platform: &str,
+    requested: &[String],
+) -> ConversionResult<Vec<String>> {
+    if requested.is_empty() {
+        return Ok(converter.get_other_platforms(source_platform));
+    }
+
+    for target_platform in requested {
+        require_platform(converter, target_platform)?;
+    }
+
+    Ok(requested.to_vec())
+}
+
+/// Convert a query to the requested target platforms
+fn run_convert(config_paths: &[PathBuf], args: ConvertArgs) -> ConversionResult<()> {
+    let query = Args::parse_query_input(args.query.as_deref())?;
+    let converter = load_converter(config_paths)?.with_missing_field_policy(args.on_missing.clone().into());
+
+    // Validate platform support
+    require_platform(&converter, &args.platform)?;
+
+    let target_platforms = resolve_targets(&converter, &args.platform, &args.to)?;
+
+    // Validate query syntax for source platform and perform conversions
+    let report = converter.convert_all(&query, &args.platform, &target_platforms)?;
+
+    // Plain output keeps stdout to the queries alone, so warnings go to stderr
+    let output_format = args.output_format();
+    if let OutputFormat::Plain = output_format {
+        for warning in &report.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        for conversion in &report.conversions {
+            for warning in &conversion.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+        }
+    }
+
+    // Generate and output results
+    let output_content = OutputFormatter::format_output(&output_format, &report)?;
+    OutputFormatter::write_output(output_content, args.output)?;
+
+    Ok(())
+}
+
+/// Convert every query of a batch file, reporting a summary on stderr
+fn run_batch(config_paths: &[PathBuf], args: BatchArgs) -> ConversionResult<()> {
+    let converter = load_converter(config_paths)?.with_missing_field_policy(args.on_missing.clone().into());
+
+    if let Some(platform) = &args.platform {
+        require_platform(&converter, platform)?;
+    }
+    for target_platform in &args.to {
+        require_platform(&converter, target_platform)?;
+    }
+
+    let entries = BatchInput::from_file(&args.input)?;
+    let processor = BatchProcessor::new(&converter, args.platform.clone(), args.to.clone());
+    let records = processor.process(&entries);
+
+    let output_content = OutputFormatter::format_batch_output(&args.format, &records)?;
+    OutputFormatter::write_output(output_content, args.output)?;
+
+    let total = records.len();
+    let failed = records.iter().filter(|record| !record.is_success()).count();
+    eprintln!("Batch completed: {} queries, {} converted, {} failed", total, total - failed, failed);
+
+    if failed > 0 {
+        return Err(ConversionError::BatchFailed { failed, total });
+    }
+
+    Ok(())
+}
+
+/// Validate a query against its platform
+fn run_validate(config_paths: &[PathBuf], args: QueryArgs) -> ConversionResult<()> {
+    let query = Args::parse_query_input(args.query.as_deref())?;
+    let converter = load_converter(config_paths)?;
+
+    require_platform(&converter, &args.platform)?;
+    let warnings = converter.validate_query_syntax(&query, &args.platform)?;
+
+    println!("Query is valid for {}", args.platform.to_uppercase());
+    for warning in warnings {
+        println!("Warning: {}", warning);
+    }
+    Ok(())
+}
+
+/// Report the platforms whose syntax accepts a query
+fn run_detect(config_paths: &[PathBuf], args: DetectArgs) -> ConversionResult<()> {
+    let query = Args::parse_query_input(args.query.as_deref())?;
+    let converter = load_converter(config_paths)?;
+
+    let matching_platforms: Vec<String> = converter
+        .get_supported_platforms()
+        .into_iter()
+        .filter(|platform| converter.validate_query_syntax(&query, platform).is_ok())
+        .collect();
+
+    if matching_platforms.is_empty() {
+        return Err(ConversionError::NoMatchingPlatform);
+    }
+
+    for platform in matching_platforms {
+        println!("{}", platform);
+    }
+
+    Ok(())
+}
+
+/// List supported platforms
+fn run_platforms(config_paths: &[PathBuf]) -> ConversionResult<()> {
+    let converter = load_converter(config_paths)?;
+
+    for platform in converter.get_supported_platforms() {
+        println!("{}", platform);
+    }
+
+    Ok(())
+}
+
+/// Print the matrix of canonical field names and their native names per platform
+fn run_fields(config_paths: &[PathBuf], args: FieldsArgs) -> ConversionResult<()> {
+    let converter = load_converter(config_paths)?;
+
+    for platform in &args.platforms {
+        require_platform(&converter, platform)?;
+    }
+
+    let output_content = if args.describe {
+        let catalog = FieldCatalog::build(converter.config_manager(), &args.platforms, &args.fields)?;
+        OutputFormatter::format_field_catalog(&args.format, &catalog)?
+    } else {
+        let mut matrix = FieldMatrix::build(converter.config_manager(), &args.platforms)?;
+        if !args.fields.is_empty() {
+            matrix.fields.retain(|row| args.fields.contains(&row.field));
+        }
+        OutputFormatter::format_field_matrix(&args.format, &matrix)?
+    };
+    OutputFormatter::write_output(output_content, args.output)?;
+
+    Ok(())
+}
+
+/// Explain how the fields of a query map to the other platforms
+fn run_explain(config_paths: &[PathBuf], args: QueryArgs) -> ConversionResult<()> {
+    let query = Args::parse_query_input(args.query.as_deref())?;
+    let converter = load_converter(config_paths)?;
+
+    require_platform(&converter, &args.platform)?;
+    converter.validate_query_syntax(&query, &args.platform)?;
+
+    let config_manager = converter.config_manager();
+    let from_config = config_manager.get_platform_config(&args.platform)?;
+
+    println!("Source platform: {}", args.platform);
+    println!("Original query: {}", query);
+    println!();
+
+    for native_name in SyntaxValidator::extract_field_names_from_query(&query) {
+        match from_config.canonical_field(&native_name) {
+            Some(field_name) => {
+                println!("[-] {} (canonical field '{}'):", native_name, field_name);
+                for target_platform in converter.get_supported_platforms() {
+                    if target_platform == args.platform {
+                        continue;
+                    }
+                    let to_config = config_manager.get_platform_config(&target_platform)?;
+                    match to_config.native_name(field_name) {
+                        Some(target_name) => println!("  {}: {}", target_platform, target_name),
+                        None => println!("  {}: (no equivalent field)", target_platform),
+                    }
+                }
+            }
+            None => println!("[-] {}: no canonical mapping, left unchanged on conversion", native_name),
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Start the interactive REPL
+fn run_repl(config_paths: &[PathBuf], args: ReplArgs) -> ConversionResult<()> {
+    let converter = load_converter(config_paths)?.with_missing_field_policy(args.on_missing.clone().into());
+
+    let source_platform = match args.platform {
+        Some(platform) => {
+            require_platform(&converter, &platform)?;
+            platform
+        }
+        None => converter
+            .get_supported_platforms()
+            .into_iter()
+            .next()
+            .ok_or_else(|| ConversionError::ConfigurationError("No platforms configured".to_string()))?,
+    };
+    for target_platform in &args.to {
+        require_platform(&converter, target_platform)?;
+    }
+
+    Repl::new(converter, source_platform, args.to, args.history).run()
+}
+
+/// Explain an error code, or list all error codes
+fn run_explain_error(args: ExplainErrorArgs) -> ConversionResult<()> {
+    let code = match args.code {
+        Some(code) => code,
+        None => {
+            for error_code in ERROR_CODES {
+                println!("{}  {:<30}  {}", error_code.code, error_code.name, error_code.summary);
+            }
+            return Ok(());
+        }
+    };
+
+    let error_code = ErrorCode::lookup(&code).ok_or(ConversionError::UnknownErrorCode(code.clone()))?;
+
+    println!("{} {}: {}", error_code.code, error_code.name, error_code.summary);
+    println!();
+    println!("{}", error_code.explanation);
+    if !error_code.example.is_empty() {
+        println!();
+        println!("Example:");
+        println!("  {}", error_code.example);
+    }
+
+    Ok(())
+}
+
+/// Check the configuration and report its issues
+fn run_config_check(config_paths: &[PathBuf], args: ConfigCheckArgs) -> ConversionResult<()> {
+    let config_manager = ConfigLoader::load_unchecked(config_paths)?;
+    let mut issues = ConfigLinter::lint(&config_manager);
+
+    // Rewrite rule tests need a configuration the converter can use
+    if !issues.iter().any(|issue| issue.severity == IssueSeverity::Error) {
+        issues.extend(QueryConverter::new(config_manager.clone()).check_rewrite_tests());
+        issues.sort_by_key(|issue| issue.severity);
+    }
+
+    let output = OutputFormatter::format_config_check(&args.format, &config_manager, &issues)?;
+    OutputFormatter::write_output(output, None)?;
+
+    let errors = issues.iter().filter(|issue| issue.severity == IssueSeverity::Error).count();
+    let warnings = issues.len() - errors;
+    if errors > 0 || (args.strict && warnings > 0) {
+        return Err(ConversionError::ConfigurationError(format!(
+            "Configuration check failed with {} errors and {} warnings",
+            errors, warnings
+        )));
+    }
+
+    Ok(())
+}
+
+/// Print the configuration layers, or the merged configuration with the source of each value
+fn run_config_show(config_paths: &[PathBuf], args: ConfigShowArgs) -> ConversionResult<()> {
+    let resolved = ConfigLoader::resolve(config_paths)?;
+    // Fail on layers that merge into an invalid configuration
+    ConfigManager::from_value(resolved.config.clone(), resolved.sources.clone())?;
+
+    let output = OutputFormatter::format_resolved_config(&args.format, &resolved, args.resolved)?;
+    OutputFormatter::write_output(output, None)
+}
+
+/// Normalize a configuration file, printing it or rewriting it in place
+fn run_config_fmt(args: ConfigFmtArgs) -> ConversionResult<()> {
+    let content = fs::read_to_string(&args.file)
+        .map_err(|e| ConversionError::IoError(format!("Failed to read '{}': {}", args.file.display(), e)))?;
+    let formatted = ConfigFormat::from_path(&args.file).normalize(&content, &args.file.display().to_string())?;
+
+    if !args.write {
+        return OutputFormatter::write_output(formatted, None);
+    }
+    if formatted != content {
+        fs::write(&args.file, formatted)
+            .map_err(|e| ConversionError::IoError(format!("Failed to write '{}': {}", args.file.display(), e)))?;
+    }
+    Ok(())
+}
+
+/// Print a shell completion script
+fn run_completions(args: CompletionsArgs) -> ConversionResult<()> {
+    OutputFormatter::write_output(generate_completions(&args.shell), None)
+}
+
+/// Print a query or query files simplified and in canonical spacing
+fn run_fmt(config_paths: &[PathBuf], args: FmtArgs) -> ConversionResult<()> {
+    let converter = load_converter(config_paths)?;
+    require_platform(&converter, &args.platform)?;
+
+    if args.files.is_empty() {
+        let query = Args::parse_query_input(args.query.as_deref())?;
+        let formatted = converter.format(&query, &args.platform, args.multiline)?;
+        if args.check {
+            if formatted != query.trim() {
+                return Err(ConversionError::NotFormatted(vec!["query".to_string()]));
+            }
+        } else {
+            println!("{}", formatted);
+        }
+        return Ok(());
+    }
+
+    let mut unformatted = Vec::new();
+    for file in &args.files {
+        let content = fs::read_to_string(file)
+            .map_err(|e| ConversionError::IoError(format!("Failed to read '{}': {}", file.display(), e)))?;
+        let formatted = format_query_file(&converter, &content, &args.platform, args.multiline, file)?;
+
+        if args.check {
+            if formatted != content {
+                unformatted.push(file.display().to_string());
+            }
+        } else if args.write {
+            if formatted != content {
+                fs::write(file, formatted)
+                    .map_err(|e| ConversionError::IoError(format!("Failed to write '{}': {}", file.display(), e)))?;
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+
+    if unformatted.is_empty() {
+        Ok(())
+    } else {
+        Err(ConversionError::NotFormatted(unformatted))
+    }
+}
+
+/// Format each query of a query file, keeping blank lines and `#` comments
+///
+/// Queries are one per line, or with `multiline` blocks of lines separated by
+/// blank lines or comments, each laid out over indented lines. Prints the
+/// location of the first query that cannot be formatted before failing.
+fn format_query_file(converter: &QueryConverter, content: &str, platform: &str, multiline: bool, path: &Path) -> ConversionResult<String> {
+    let mut output = String::with_capacity(content.len());
+    // First line number and lines of the query being read
+    let mut block: Option<(usize, Vec<&str>)> = None;
+
+    let flush = |block: &mut Option<(usize, Vec<&str>)>, output: &mut String| -> ConversionResult<()> {
+        let Some((number, lines)) = block.take() else {
+            return Ok(());
+        };
+        let query: String = lines.concat();
+        let formatted = converter.format(query.trim(), platform, multiline).inspect_err(|e| {
+            // Report the line of the offending part of a query spanning several lines
+            let leading = query.len() - query.trim_start().len();
+            let line = ErrorReport::from(e).span.map_or(number, |span| {
+                number + query.get(..leading + span.start).map_or(0, |before| before.matches('\n').count())
+            });
+            eprintln!("Error in {}:{}", path.display(), line);
+        })?;
+        output.push_str(&formatted);
+        if query.ends_with('\n') {
+            output.push('\n');
+        }
+        Ok(())
+    };
+
+    for (number, line) in content.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            flush(&mut block, &mut output)?;
+            output.push_str(line);
+            continue;
+        }
+        block.get_or_insert_with(|| (number + 1, Vec::new())).1.push(line);
+        if !multiline {
+            flush(&mut block, &mut output)?;
+        }
+    }
+    flush(&mut block, &mut output)?;
+
+    Ok(output)
+}
+
+/// Compare two queries and print a record telling them apart
+fn run_equiv(config_paths: &[PathBuf], args: EquivArgs) -> ConversionResult<()> {
+    // stdin holds a single query, the second read would find it empty
+    if args.query_a == "-" && args.query_b == "-" {
+        return Err(ConversionError::MissingQuery("Only one of -a and -b can be read from stdin".to_string()));
+    }
+    let query_a = Args::parse_query_input(Some(&args.query_a))?;
+    let query_b = Args::parse_query_input(Some(&args.query_b))?;
+    let converter = load_converter(config_paths)?;
+
+    require_platform(&converter, &args.platform_a)?;
+    require_platform(&converter, &args.platform_b)?;
+
+    match converter.equivalence(&query_a, &args.platform_a, &query_b, &args.platform_b)? {
+        Equivalence::Equal => {
+            println!("Equivalent");
+            Ok(())
+        }
+        Equivalence::Different { matching, matches_a } => {
+            let (matched, unmatched) = if matches_a { ("A", "B") } else { ("B", "A") };
+            if matching.is_empty() {
+                println!("Query {} matches a record matching none of the conditions, query {} does not", matched, unmatched);
+            } else {
+                println!("Query {} matches a record matching only these conditions, query {} does not:", matched, unmatched);
+                for condition in matching {
+                    println!("  {}", condition);
+                }
+            }
+            Err(ConversionError::NotEquivalent)
+        }
+    }
+}
+
+/// Print completion candidates, one per line
+fn run_complete(config_paths: &[PathBuf], args: CompleteArgs) -> ConversionResult<()> {
+    let converter = load_converter(config_paths)?;
+
+    for candidate in completion_candidates(&converter, &args)? {
+        println!("{}", candidate);
+    }
+
+    Ok(())
+}
+
+/// Candidates of the hidden `__complete` command
+fn completion_candidates(converter: &QueryConverter, args: &CompleteArgs) -> ConversionResult<Vec<String>> {
+    match args.kind {
+        CompletionKind::Platforms => {
+            let mut platforms = converter.get_supported_platforms();
+            platforms.push(CANONICAL_PLATFORM.to_string());
+            Ok(platforms)
+        }
+        CompletionKind::Fields => Ok(converter.config_manager().get_platform_config(&args.platform)?.field_names()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+
+    fn converter() -> QueryConverter {
+        QueryConverter::new(ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap())
+    }
+
+    #[test]
+    fn fields_are_completed_with_the_names_of_the_platform() {
+        let converter = converter();
+        let complete = |platform: &str| {
+            completion_candidates(&converter, &CompleteArgs { kind: CompletionKind::Fields, platform: platform.to_string() })
+        };
+        assert!(complete("hunter").unwrap().iter().any(|name| name == "web.title"));
+        assert!(complete(CANONICAL_PLATFORM).unwrap().iter().any(|name| name == "title"));
+        assert!(matches!(complete("nowhere"), Err(ConversionError::UnsupportedPlatform(_))));
+
+        let platforms = completion_candidates(&converter, &CompleteArgs { kind: CompletionKind::Platforms, platform: String::new() }).unwrap();
+        assert!(platforms.iter().any(|platform| platform == CANONICAL_PLATFORM));
+    }
+
+    #[test]
+    fn equiv_reads_at_most_one_query_from_stdin() {
+        let args = EquivArgs { query_a: "-".to_string(), platform_a: "fofa".to_string(), query_b: "-".to_string(), platform_b: "quake".to_string() };
+        assert!(matches!(run_equiv(&[], args), Err(ConversionError::MissingQuery(_))));
+    }
+
+    #[test]
+    fn query_files_keep_comments_and_blank_lines() {
+        let content = "# web\n((title=\"a\"))  &&  port=\"80\"\n\ntitle=\"b\"";
+        let formatted = format_query_file(&converter(), content, "fofa", false, Path::new("q.txt")).unwrap();
+        assert_eq!(formatted, "# web\ntitle=\"a\" && port=\"80\"\n\ntitle=\"b\"");
+    }
+
+    #[test]
+    fn multiline_query_files_separate_queries_by_blank_lines() {
+        let content = "# web\ntitle=\"a\" && (port=\"80\" || (port=\"443\" && country=\"CN\"))\n\n# ssh\nport=\"22\"\n  && title=\"b\"\n";
+        let formatted = format_query_file(&converter(), content, "fofa", true, Path::new("q.txt")).unwrap();
+        let expected = "# web\ntitle=\"a\"\n&& (\n  port=\"80\"\n  || (port=\"443\" && country=\"CN\")\n)\n\n# ssh\nport=\"22\"\n&& title=\"b\"\n";
+        assert_eq!(formatted, expected);
+        // The layout is stable, so `fmt -m --check` accepts formatted files
+        assert_eq!(format_query_file(&converter(), expected, "fofa", true, Path::new("q.txt")).unwrap(), expected);
+    }
+}
@@ -0,0 +1,150 @@
+use clap::CommandFactory;
+use clap_complete::{generate, Shell};
+use crate::cli::{Args, CompletionShell};
+
+/// Binary name the completion scripts are registered for
+const BIN_NAME: &str = "convertix";
+
+/// Bash wrapper completing platform values and query fields from the loaded configuration
+const BASH_DYNAMIC: &str = r#"
+_convertix_dynamic() {
+    local cur prev i platform="" config_args=()
+    cur="${COMP_WORDS[COMP_CWORD]}"
+    prev="${COMP_WORDS[COMP_CWORD-1]}"
+
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case "${COMP_WORDS[i]}" in
+            -c|--config) config_args+=(--config "${COMP_WORDS[i+1]}") ;;
+            -p|--platform) platform="${COMP_WORDS[i+1]}" ;;
+        esac
+    done
+
+    case "${prev}" in
+        -p|--platform|-t|--to)
+            local prefix=""
+            if [[ "${cur}" == *,* ]]; then
+                prefix="${cur%,*},"
+            fi
+            COMPREPLY=($(compgen -P "${prefix}" -W "$(convertix "${config_args[@]}" __complete platforms 2>/dev/null)" -- "${cur##*,}"))
+            return 0
+            ;;
+        -q|--query)
+            # Complete the field name being typed at the end of the query
+            local word="${cur##*[^[:alnum:]_.]}"
+            local prefix="${cur%"${word}"}"
+            COMPREPLY=($(compgen -P "${prefix}" -W "$(convertix "${config_args[@]}" __complete fields --platform "${platform:-convertix}" 2>/dev/null)" -- "${word}"))
+            compopt -o nospace 2>/dev/null
+            return 0
+            ;;
+    esac
+
+    _convertix "$@"
+}
+
+complete -F _convertix_dynamic -o nosort -o bashdefault -o default convertix
+"#;
+
+/// Zsh wrapper completing platform values and query fields from the loaded configuration
+///
+/// It registers itself after the generated script, so it replaces the registration of `_convertix`.
+const ZSH_DYNAMIC: &str = r#"
+_convertix_dynamic() {
+    local -a config_args candidates
+    local i platform=convertix
+    for ((i = 2; i < CURRENT; i++)); do
+        case "${words[i]}" in
+            -c|--config) config_args+=(--config "${words[i+1]}") ;;
+            -p|--platform) platform="${words[i+1]}" ;;
+        esac
+    done
+
+    case "${words[CURRENT-1]}" in
+        -p|--platform|-t|--to)
+            candidates=(${(f)"$(convertix "${config_args[@]}" __complete platforms 2>/dev/null)"})
+            compset -P '*,'
+            compadd -a candidates
+            return
+            ;;
+        -q|--query)
+            candidates=(${(f)"$(convertix "${config_args[@]}" __complete fields --platform "${platform}" 2>/dev/null)"})
+            compset -P '*[^[:alnum:]_.]'
+            compadd -S '' -a candidates
+            return
+            ;;
+    esac
+
+    _convertix "$@"
+}
+
+compdef _convertix_dynamic convertix
+"#;
+
+/// Fish completions of platform values and query fields from the loaded configuration
+const FISH_DYNAMIC: &str = r#"
+function __convertix_complete
+    set -l tokens (commandline -opc)
+    set -l args
+    set -l platform convertix
+    for i in (seq (count $tokens))
+        if contains -- $tokens[$i] -c --config
+            set -a args --config $tokens[(math $i + 1)]
+        else if contains -- $tokens[$i] -p --platform
+            set platform $tokens[(math $i + 1)]
+        end
+    end
+    if test "$argv[1]" = fields
+        # Keep the query typed so far in front of each field name
+        set -l prefix (string replace -r '[[:alnum:]_.]*$' '' -- (commandline -ct))
+        convertix $args __complete fields --platform $platform 2>/dev/null | string replace -r '^' -- $prefix
+    else
+        convertix $args __complete platforms 2>/dev/null
+    end
+end
+
+complete -c convertix -s p -l platform -f -a '(__convertix_complete platforms)'
+complete -c convertix -s t -l to -f -a '(__convertix_complete platforms)'
+complete -c convertix -s q -l query -f -a '(__convertix_complete fields)'
+"#;
+
+/// Generate the completion script for a shell
+///
+/// The static part is generated from the clap definition; a shell-specific
+/// wrapper then completes `--platform`/`--to` values and the field names of
+/// `--query` by calling the hidden `convertix __complete` command, so custom
+/// platforms and fields in the configuration are offered as well.
+pub fn generate_completions(shell: &CompletionShell) -> String {
+    let (clap_shell, dynamic) = match shell {
+        CompletionShell::Bash => (Shell::Bash, BASH_DYNAMIC),
+        CompletionShell::Zsh => (Shell::Zsh, ZSH_DYNAMIC),
+        CompletionShell::Fish => (Shell::Fish, FISH_DYNAMIC),
+    };
+
+    let mut buffer = Vec::new();
+    generate(clap_shell, &mut Args::command(), BIN_NAME, &mut buffer);
+
+    let mut script = String::from_utf8_lossy(&buffer).into_owned();
+    script.push_str(dynamic);
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_complete_platforms_and_query_fields() {
+        for shell in [CompletionShell::Bash, CompletionShell::Zsh, CompletionShell::Fish] {
+            let script = generate_completions(&shell);
+            assert!(script.contains("__complete platforms"));
+            assert!(script.contains("__complete fields --platform"));
+        }
+    }
+
+    #[test]
+    fn zsh_registers_the_wrapper_last() {
+        let script = generate_completions(&CompletionShell::Zsh);
+        let last_compdef = script.lines().rfind(|line| line.trim_start().starts_with("compdef "));
+        assert_eq!(last_compdef, Some("compdef _convertix_dynamic convertix"));
+        assert!(script.find("_convertix_dynamic() {") > script.find("_convertix() {"));
+    }
+}
@@ -1,3 +1,9 @@
 pub mod args;
+pub mod commands;
+pub mod completions;
+pub mod repl;
 
 pub use args::*;
+pub use commands::*;
+pub use completions::*;
+pub use repl::*;
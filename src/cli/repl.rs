@@ -0,0 +1,332 @@
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::env;
+use std::path::PathBuf;
+use crate::config::FieldMatrix;
+use crate::converter::QueryConverter;
+use crate::error::{ConversionError, ConversionResult};
+
+/// REPL commands offered for completion
+const REPL_COMMANDS: &[&str] = &[":from", ":to", ":fields", ":platforms", ":help", ":quit"];
+
+/// Interactive session converting each entered query to the target platforms
+pub struct Repl {
+    converter: QueryConverter,
+    source_platform: String,
+    target_platforms: Vec<String>,
+    history_path: Option<PathBuf>,
+}
+
+/// Line editor helper completing commands, platforms and field names
+struct ReplHelper {
+    platforms: Vec<String>,
+    fields: Vec<String>,
+}
+
+impl Repl {
+    /// Create a REPL session; an empty target list converts to all other platforms
+    pub fn new(
+        converter: QueryConverter,
+        source_platform: String,
+        target_platforms: Vec<String>,
+        history_path: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            converter,
+            source_platform,
+            target_platforms,
+            history_path: history_path.or_else(Self::default_history_path),
+        }
+    }
+
+    /// Default history file: `CONVERTIX_HISTORY`, else `~/.convertix_history`
+    fn default_history_path() -> Option<PathBuf> {
+        env::var_os("CONVERTIX_HISTORY")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".convertix_history")))
+    }
+
+    /// Run the read-eval-print loop until EOF or `:quit`
+    pub fn run(&mut self) -> ConversionResult<()> {
+        let mut editor: Editor<ReplHelper, DefaultHistory> = Editor::new()
+            .map_err(|e| ConversionError::IoError(format!("Failed to start line editor: {}", e)))?;
+        editor.set_helper(Some(ReplHelper {
+            platforms: self.converter.get_supported_platforms(),
+            fields: self.source_fields(),
+        }));
+
+        if let Some(path) = &self.history_path {
+            // A missing history file is expected on first use
+            let _ = editor.load_history(path);
+        }
+
+        println!("ConvertiX REPL, type :help for commands and :quit to exit");
+
+        loop {
+            let prompt = format!("{}> ", self.source_platform);
+            let line = match editor.readline(&prompt) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(ConversionError::IoError(format!("Failed to read input: {}", e))),
+            };
+
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let _ = editor.add_history_entry(line);
+            // Save each line at once, so history survives a killed session
+            if let Err(e) = self.save_history(&mut editor) {
+                self.print_error(&e);
+            }
+
+            if let Some(command) = line.strip_prefix(':') {
+                if !self.run_command(command) {
+                    break;
+                }
+                if let Some(helper) = editor.helper_mut() {
+                    helper.fields = self.source_fields();
+                }
+            } else {
+                self.convert_query(line);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the history of the line editor to the history file, if any
+    fn save_history(&self, editor: &mut Editor<ReplHelper, DefaultHistory>) -> ConversionResult<()> {
+        match &self.history_path {
+            Some(path) => editor
+                .save_history(path)
+                .map_err(|e| ConversionError::IoError(format!("Failed to save history '{}': {}", path.display(), e))),
+            None => Ok(()),
+        }
+    }
+
+    /// Run a `:` command, returning false when the session should end
+    fn run_command(&mut self, command: &str) -> bool {
+        let mut parts = command.split_whitespace();
+        let name = parts.next().unwrap_or("");
+        let argument = parts.collect::<Vec<_>>().join(" ");
+
+        match name {
+            "from" => {
+                if self.converter.is_platform_supported(&argument) {
+                    self.source_platform = argument;
+                } else {
+                    self.print_error(&ConversionError::UnsupportedPlatform(argument));
+                }
+            }
+            "to" => {
+                let platforms: Vec<String> = argument
+                    .split(',')
+                    .map(|platform| platform.trim().to_string())
+                    .filter(|platform| !platform.is_empty() && platform != "all")
+                    .collect();
+                match platforms.iter().find(|platform| !self.converter.is_platform_supported(platform)) {
+                    Some(platform) => self.print_error(&ConversionError::UnsupportedPlatform(platform.clone())),
+                    None => self.target_platforms = platforms,
+                }
+            }
+            "fields" => self.print_fields(&argument),
+            "platforms" => {
+                for platform in self.converter.get_supported_platforms() {
+                    println!("{}", platform);
+                }
+            }
+            "help" => Self::print_help(),
+            "quit" | "q" | "exit" => return false,
+            _ => println!("Unknown command ':{}', type :help for commands", name),
+        }
+
+        true
+    }
+
+    /// Validate a query and print its conversions
+    fn convert_query(&self, query: &str) {
+        let target_platforms = if self.target_platforms.is_empty() {
+            self.converter.get_other_platforms(&self.source_platform)
+        } else {
+            self.target_platforms.clone()
+        };
+
+        match self.converter.convert_all(query, &self.source_platform, &target_platforms) {
+            Ok(report) => {
+                println!("Valid {} query", self.source_platform.to_uppercase());
+                for warning in &report.warnings {
+                    println!("Warning: {}", warning);
+                }
+                for conversion in &report.conversions {
+                    println!("[-] {}: {}", conversion.platform.to_uppercase(), conversion.query);
+                    for warning in &conversion.warnings {
+                        println!("    Warning: {}", warning);
+                    }
+                }
+            }
+            Err(e) => self.print_error(&e),
+        }
+    }
+
+    /// Print the field mapping of the active platform, optionally filtered by a field name
+    fn print_fields(&self, filter: &str) {
+        match self.field_lines(filter) {
+            Ok(lines) => lines.iter().for_each(|line| println!("{}", line)),
+            Err(e) => self.print_error(&e),
+        }
+    }
+
+    /// Lines listing each field as `canonical (native)`, filtered by the native names and aliases of the active platform
+    ///
+    /// The filter matches the names offered by tab completion; matching fields also show their name on every platform.
+    fn field_lines(&self, filter: &str) -> ConversionResult<Vec<String>> {
+        let matrix = FieldMatrix::build(self.converter.config_manager(), &[])?;
+        let source_config = self.converter.config_manager().get_platform_config(&self.source_platform)?;
+        let mut lines = Vec::new();
+
+        for row in &matrix.fields {
+            let spec = source_config.fields.get(&row.field);
+            let matches = filter.is_empty()
+                || spec.is_some_and(|spec| std::iter::once(&spec.name).chain(&spec.aliases).any(|name| name.contains(filter)));
+            if !matches {
+                continue;
+            }
+
+            lines.push(format!("{} ({})", row.field, spec.map(|spec| spec.name.as_str()).unwrap_or("-")));
+            if !filter.is_empty() {
+                for (platform, native_name) in &row.platforms {
+                    lines.push(format!("    {}: {}", platform, native_name.as_deref().unwrap_or("-")));
+                }
+            }
+        }
+
+        Ok(lines)
+    }
+
+    /// Print an error with its code
+    fn print_error(&self, error: &ConversionError) {
+        println!("ERROR[{}]: {}", error.code().code, error);
+    }
+
+    /// Print the list of REPL commands
+    fn print_help() {
+        println!(":from <platform>      Set the source platform");
+        println!(":to <p1,p2,...|all>   Set the target platforms");
+        println!(":fields [name]        List fields, or show how fields with a matching native name map to each platform");
+        println!(":platforms            List supported platforms");
+        println!(":help                 Show this help");
+        println!(":quit                 Exit the REPL");
+        println!("Any other input is validated and converted as a query");
+    }
+
+    /// Native field names of the active source platform
+    fn source_fields(&self) -> Vec<String> {
+        self.converter
+            .config_manager()
+            .get_platform_config(&self.source_platform)
+            .map(|config| config.field_names())
+            .unwrap_or_default()
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before_cursor = &line[..pos];
+        let start = before_cursor
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.' || c == ':'))
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let word = &before_cursor[start..];
+
+        if start == 0 && word.starts_with(':') {
+            return Ok((start, Self::pairs(REPL_COMMANDS.iter().copied(), word)));
+        }
+
+        if before_cursor.starts_with(":from ") || before_cursor.starts_with(":to ") {
+            // Platform lists after :to are comma separated
+            let start = before_cursor.rfind([' ', ',']).map(|index| index + 1).unwrap_or(0);
+            let word = &before_cursor[start..];
+            return Ok((start, Self::pairs(self.platforms.iter().map(String::as_str), word)));
+        }
+
+        Ok((start, Self::pairs(self.fields.iter().map(String::as_str), word)))
+    }
+}
+
+impl ReplHelper {
+    /// Build completion candidates starting with the typed word
+    fn pairs<'a>(candidates: impl Iterator<Item = &'a str>, word: &str) -> Vec<Pair> {
+        candidates
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| Pair {
+                display: candidate.to_string(),
+                replacement: candidate.to_string(),
+            })
+            .collect()
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigManager, EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+
+    fn repl() -> Repl {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        Repl::new(QueryConverter::new(manager), "fofa".to_string(), Vec::new(), Some(PathBuf::new()))
+    }
+
+    #[test]
+    fn from_switches_only_to_known_platforms() {
+        let mut repl = repl();
+        assert!(repl.run_command("from quake"));
+        assert_eq!(repl.source_platform, "quake");
+        assert!(repl.run_command("from nowhere"));
+        assert_eq!(repl.source_platform, "quake");
+    }
+
+    #[test]
+    fn to_sets_target_lists_and_all_clears_them() {
+        let mut repl = repl();
+        repl.run_command("to quake, hunter");
+        assert_eq!(repl.target_platforms, ["quake", "hunter"]);
+        repl.run_command("to quake,nowhere");
+        assert_eq!(repl.target_platforms, ["quake", "hunter"]);
+        repl.run_command("to all");
+        assert!(repl.target_platforms.is_empty());
+        assert!(!repl.run_command("quit"));
+    }
+
+    #[test]
+    fn fields_filter_by_the_names_offered_for_completion() {
+        let mut repl = repl();
+        let lines = repl.field_lines("").unwrap();
+        assert!(lines.iter().any(|line| line == "ip (ip)"), "{:?}", lines);
+
+        repl.run_command("from quake");
+        assert!(repl.source_fields().iter().any(|name| name == "service"));
+        let lines = repl.field_lines("service").unwrap();
+        assert!(lines.iter().any(|line| line == "protocol (service)"), "{:?}", lines);
+        assert!(lines.iter().any(|line| line == "    fofa: protocol"), "{:?}", lines);
+        assert!(repl.field_lines("protocol").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,325 @@
+use serde::de::{self, value::MapAccessDeserializer, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+
+/// Type of the values a field accepts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    /// Any text
+    #[default]
+    String,
+    /// Integer or decimal number, or a range such as `[80 TO 443]`
+    Number,
+    /// IPv4 or IPv6 address, optionally with a `/prefix` CIDR suffix
+    Ip,
+    /// Date in `YYYY-MM-DD` form, or a range such as `[2024-01-01 TO *]`
+    Date,
+    /// `true` or `false`
+    Boolean,
+}
+
+impl FieldType {
+    /// Check whether a query value is valid for this type
+    ///
+    /// Number and date fields also accept a range `[from TO to]`, with `{` or `}`
+    /// for an exclusive bound and `*` for an open one.
+    pub fn accepts(&self, value: &str) -> bool {
+        if let (FieldType::Number | FieldType::Date, Some((from, to))) = (self, Self::range_bounds(value)) {
+            return [from, to].iter().all(|bound| *bound == "*" || self.accepts(bound));
+        }
+
+        match self {
+            FieldType::String => true,
+            FieldType::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+            FieldType::Ip => {
+                let (address, prefix) = match value.split_once('/') {
+                    Some((address, prefix)) => (address, Some(prefix)),
+                    None => (value, None),
+                };
+                address.parse::<IpAddr>().is_ok() && prefix.is_none_or(|prefix| prefix.parse::<u8>().is_ok())
+            }
+            FieldType::Date => {
+                let parts: Vec<&str> = value.split('-').collect();
+                matches!(parts.as_slice(), [year, month, day]
+                    if year.len() == 4 && month.len() == 2 && day.len() == 2
+                        && parts.iter().all(|part| part.bytes().all(|b| b.is_ascii_digit())))
+            }
+            FieldType::Boolean => value == "true" || value == "false",
+        }
+    }
+
+    /// Split a range value such as `[80 TO 443]` into its bounds
+    fn range_bounds(value: &str) -> Option<(&str, &str)> {
+        let inner = value.strip_prefix(['[', '{'])?.strip_suffix([']', '}'])?;
+        let (from, to) = inner.split_once(" TO ")?;
+        Some((from.trim(), to.trim()))
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FieldType::String => "string",
+            FieldType::Number => "number",
+            FieldType::Ip => "ip",
+            FieldType::Date => "date",
+            FieldType::Boolean => "boolean",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How a field can be matched against a value, named after the platform operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    /// `field=value` or `field:value`
+    Equal,
+    /// `field!=value` or `NOT field:value`
+    NotEqual,
+    /// `field==value`, matching the whole value
+    Exact,
+}
+
+impl fmt::Display for MatchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchMode::Equal => write!(f, "equal"),
+            MatchMode::NotEqual => write!(f, "not_equal"),
+            MatchMode::Exact => write!(f, "exact"),
+        }
+    }
+}
+
+/// What the converter does with a clause whose field has no equivalent on the target platform
+///
+/// An `approximations` entry of the field for the target platform takes
+/// precedence over the policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingFieldPolicy {
+    /// Leave the clause unchanged and warn; the target platform will likely reject it
+    #[default]
+    Keep,
+    /// Remove the clause and warn; the converted query matches more, or less under a negation
+    Drop,
+    /// Reject the conversion
+    Error,
+}
+
+impl fmt::Display for MissingFieldPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MissingFieldPolicy::Keep => write!(f, "keep"),
+            MissingFieldPolicy::Drop => write!(f, "drop"),
+            MissingFieldPolicy::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Replacement of a clause on a platform without an equivalent field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Approximation {
+    /// Canonical field of the target platform to match instead
+    pub field: String,
+    /// Value to match instead; the value of the query when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+/// Platform-specific description of a canonical field
+///
+/// In the configuration a field is either the native name as a plain string,
+/// or an object with the native name and further metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "FieldEntry")]
+pub struct FieldSpec {
+    /// Native field name on the platform
+    pub name: String,
+    /// Other names the platform accepts for the field
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Example values
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+    /// Supported ways of matching the field; all modes when empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub match_modes: Vec<MatchMode>,
+    /// Field is only available to paid (VIP) accounts
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub vip: bool,
+    /// Field exists only on this platform and is not expected elsewhere
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub platform_only: bool,
+    /// Policy when converting to a platform without the field; the converter default when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_missing: Option<MissingFieldPolicy>,
+    /// Replacement clauses on platforms without the field, by target platform
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub approximations: BTreeMap<String, Approximation>,
+    /// Field searches large text such as the response body and matches many records on its own
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub broad: bool,
+    /// Values of the field are matched case-sensitively
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub case_sensitive: bool,
+}
+
+/// Field configuration as written in the configuration file: the native name, or an object
+enum FieldEntry {
+    Name(String),
+    Spec(FieldObject),
+}
+
+/// Field configuration written as an object with the native name and further metadata
+///
+/// Unknown keys are rejected, so that a misspelled key is reported instead of ignored.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldObject {
+    name: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default, rename = "type")]
+    field_type: FieldType,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    examples: Vec<String>,
+    #[serde(default)]
+    match_modes: Vec<MatchMode>,
+    #[serde(default)]
+    vip: bool,
+    #[serde(default)]
+    platform_only: bool,
+    #[serde(default)]
+    on_missing: Option<MissingFieldPolicy>,
+    #[serde(default)]
+    approximations: BTreeMap<String, Approximation>,
+    #[serde(default)]
+    broad: bool,
+    #[serde(default)]
+    case_sensitive: bool,
+}
+
+impl<'de> Deserialize<'de> for FieldEntry {
+    /// Read a string as the native name and an object as a [`FieldObject`], keeping the error of the object
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntryVisitor;
+
+        impl<'de> Visitor<'de> for EntryVisitor {
+            type Value = FieldEntry;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a native field name or an object with a 'name'")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<FieldEntry, E> {
+                Ok(FieldEntry::Name(name.to_string()))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<FieldEntry, A::Error> {
+                FieldObject::deserialize(MapAccessDeserializer::new(map)).map(FieldEntry::Spec)
+            }
+        }
+
+        deserializer.deserialize_any(EntryVisitor)
+    }
+}
+
+impl From<FieldEntry> for FieldSpec {
+    fn from(entry: FieldEntry) -> Self {
+        match entry {
+            FieldEntry::Name(name) => FieldSpec::new(name),
+            FieldEntry::Spec(object) => FieldSpec {
+                name: object.name,
+                aliases: object.aliases,
+                field_type: object.field_type,
+                description: object.description,
+                examples: object.examples,
+                match_modes: object.match_modes,
+                vip: object.vip,
+                platform_only: object.platform_only,
+                on_missing: object.on_missing,
+                approximations: object.approximations,
+                broad: object.broad,
+                case_sensitive: object.case_sensitive,
+            },
+        }
+    }
+}
+
+impl FieldSpec {
+    /// Create a string field with no further metadata
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            aliases: Vec::new(),
+            field_type: FieldType::String,
+            description: None,
+            examples: Vec::new(),
+            match_modes: Vec::new(),
+            vip: false,
+            platform_only: false,
+            on_missing: None,
+            approximations: BTreeMap::new(),
+            broad: false,
+            case_sensitive: false,
+        }
+    }
+
+    /// Check whether the field can be matched with a mode
+    pub fn supports(&self, mode: MatchMode) -> bool {
+        self.match_modes.is_empty() || self.match_modes.contains(&mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_accept_ranges() {
+        assert!(FieldType::Number.accepts("80"));
+        assert!(FieldType::Number.accepts("[80 TO 443]"));
+        assert!(FieldType::Number.accepts("{80 TO *]"));
+        assert!(!FieldType::Number.accepts("[80 TO http]"));
+        assert!(!FieldType::Number.accepts("[80"));
+        assert!(!FieldType::Number.accepts("[80 443]"));
+    }
+
+    #[test]
+    fn numbers_are_finite() {
+        assert!(FieldType::Number.accepts("1.5e3"));
+        for value in ["inf", "-infinity", "NaN", "1e400"] {
+            assert!(!FieldType::Number.accepts(value), "{}", value);
+        }
+    }
+
+    #[test]
+    fn fields_are_names_or_objects_without_unknown_keys() {
+        let spec: FieldSpec = serde_json::from_str(r#""title""#).unwrap();
+        assert_eq!(spec, FieldSpec::new("title"));
+        let spec: FieldSpec = serde_json::from_str(r#"{"name": "port", "type": "number", "on_missing": "drop"}"#).unwrap();
+        assert_eq!((spec.field_type, spec.on_missing), (FieldType::Number, Some(MissingFieldPolicy::Drop)));
+
+        let error = serde_json::from_str::<FieldSpec>(r#"{"name": "port", "on_mising": "drop"}"#).unwrap_err().to_string();
+        assert!(error.contains("unknown field `on_mising`"), "{}", error);
+        let error = serde_json::from_str::<FieldSpec>("80").unwrap_err().to_string();
+        assert!(error.contains("a native field name or an object"), "{}", error);
+    }
+
+    #[test]
+    fn dates_accept_ranges() {
+        assert!(FieldType::Date.accepts("[2024-01-01 TO *]"));
+        assert!(!FieldType::Date.accepts("[2024 TO 2025]"));
+        assert!(!FieldType::Ip.accepts("[1.1.1.1 TO 1.1.1.9]"));
+    }
+}
@@ -0,0 +1,573 @@
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, Table};
+use crate::config::{EXTENDS_KEY, SCHEMA_KEY};
+use crate::error::{ConversionError, ConversionResult};
+
+/// Operator keys in the order they are written by `config fmt`
+const OPERATOR_ORDER: &[&str] = &["equal", "exact", "and", "or", "not_equal", "left_paren", "right_paren"];
+
+/// Platform keys in the order they are written by `config fmt`
+const PLATFORM_KEY_ORDER: &[&str] = &["operators", "fields", "search_url", "rewrites", "query_lint"];
+
+/// Field metadata keys in the order they are written by `config fmt`
+const FIELD_KEY_ORDER: &[&str] = &["name", "aliases", "type", "description", "examples", "match_modes", "vip", "platform_only", "broad", "case_sensitive", "on_missing", "approximations"];
+
+/// Rewrite rule keys in the order they are written by `config fmt`
+const REWRITE_KEY_ORDER: &[&str] = &["from", "to", "description", "tests"];
+
+/// Rewrite rule test keys in the order they are written by `config fmt`
+const REWRITE_TEST_KEY_ORDER: &[&str] = &["query", "expected"];
+
+/// Syntax of a configuration file, chosen by its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// File extensions recognized for each format, in discovery order
+    pub const EXTENSIONS: &'static [&'static str] = &["json", "toml", "yaml", "yml"];
+
+    /// Pick the format from the file extension; unknown extensions are read as JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("toml") => ConfigFormat::Toml,
+            Some(extension) if extension.eq_ignore_ascii_case("yaml") || extension.eq_ignore_ascii_case("yml") => {
+                ConfigFormat::Yaml
+            }
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    /// Parse configuration text into a JSON value
+    pub fn parse(&self, content: &str, source: &str) -> ConversionResult<Value> {
+        let parsed = match self {
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml_edit::de::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+        };
+
+        parsed.map_err(|e| {
+            ConversionError::ConfigurationError(format!("Failed to parse config file '{}': {}", source, e))
+        })
+    }
+
+    /// Normalize configuration text: sort platforms and fields, order operators, keep comments
+    ///
+    /// The result is checked to parse to the same configuration as the input.
+    pub fn normalize(&self, content: &str, source: &str) -> ConversionResult<String> {
+        let original = self.parse(content, source)?;
+
+        let normalized = match self {
+            ConfigFormat::Json => Self::to_json(&original)?,
+            ConfigFormat::Toml => {
+                let mut document: DocumentMut = content.parse().map_err(|e| {
+                    ConversionError::ConfigurationError(format!("Failed to parse config file '{}': {}", source, e))
+                })?;
+                normalize_toml_table(document.as_table_mut(), &mut Vec::new(), &mut 0);
+                // A table moved to the top keeps the blank line that separated it from the one before
+                document.to_string().trim_start_matches('\n').to_string()
+            }
+            ConfigFormat::Yaml => match normalize_yaml(content) {
+                Some(normalized) => normalized,
+                // Re-serializing is only lossless when there are no comments to keep
+                None if !content.contains('#') => serde_yaml::to_string(&OrderedValue::root(&original))
+                    .map_err(|e| ConversionError::SerializationError(format!("Failed to serialize YAML: {}", e)))?,
+                None => {
+                    return Err(ConversionError::ConfigurationError(format!(
+                        "Cannot format '{}' without losing comments: only block mappings and lists of \
+                         single-line values or mappings are supported",
+                        source
+                    )))
+                }
+            },
+        };
+
+        if self.parse(&normalized, source)? != original {
+            return Err(ConversionError::InternalError(format!(
+                "Formatting '{}' would change its meaning; the file was left unchanged",
+                source
+            )));
+        }
+
+        Ok(normalized)
+    }
+
+    /// Serialize a configuration as pretty JSON in `config fmt` key order
+    fn to_json(config: &Value) -> ConversionResult<String> {
+        serde_json::to_string_pretty(&OrderedValue::root(config))
+            .map(|json| json + "\n")
+            .map_err(|e| ConversionError::SerializationError(format!("Failed to serialize JSON: {}", e)))
+    }
+}
+
+/// Sort key of a configuration key, given the keys of its parents
+fn key_rank(parents: &[String], key: &str) -> (usize, String) {
+    let position = |order: &[&str]| order.iter().position(|candidate| *candidate == key).unwrap_or(order.len());
+
+    let rank = match parents {
+        [] if key == SCHEMA_KEY => 0,
+        [] if key == EXTENDS_KEY => 1,
+        [] => 2,
+        [_platform] => position(PLATFORM_KEY_ORDER),
+        [_platform, section] if section == "operators" => position(OPERATOR_ORDER),
+        [_platform, section, _field] if section == "fields" => position(FIELD_KEY_ORDER),
+        [_platform, section, _target] if section == "rewrites" => position(REWRITE_KEY_ORDER),
+        [_platform, section, _target, tests] if section == "rewrites" && tests == "tests" => {
+            position(REWRITE_TEST_KEY_ORDER)
+        }
+        _ => 0,
+    };
+
+    (rank, key.to_string())
+}
+
+/// Compare two keys below the same parents in `config fmt` order
+fn compare_keys(parents: &[String], a: &str, b: &str) -> Ordering {
+    key_rank(parents, a).cmp(&key_rank(parents, b))
+}
+
+/// JSON value serialized with its object keys in `config fmt` order
+struct OrderedValue<'a> {
+    value: &'a Value,
+    parents: Vec<String>,
+}
+
+impl<'a> OrderedValue<'a> {
+    fn root(value: &'a Value) -> Self {
+        Self {
+            value,
+            parents: Vec::new(),
+        }
+    }
+}
+
+impl Serialize for OrderedValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.value {
+            Value::Object(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|(a, _), (b, _)| compare_keys(&self.parents, a, b));
+
+                let mut serialized = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    let mut parents = self.parents.clone();
+                    parents.push(key.clone());
+                    serialized.serialize_entry(key, &OrderedValue { value, parents })?;
+                }
+                serialized.end()
+            }
+            Value::Array(items) => {
+                let mut serialized = serializer.serialize_seq(Some(items.len()))?;
+                for value in items {
+                    serialized.serialize_element(&OrderedValue {
+                        value,
+                        parents: self.parents.clone(),
+                    })?;
+                }
+                serialized.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// Sort the keys of a TOML table and lay out its sub-tables in the same order
+fn normalize_toml_table(table: &mut Table, parents: &mut Vec<String>, position: &mut usize) {
+    if !parents.is_empty() && !table.is_dotted() {
+        table.set_position(*position);
+        *position += 1;
+
+        // Separate each written table from the one before by one blank line, above its comments
+        if !table.is_implicit() {
+            let prefix = table.decor().prefix().and_then(|prefix| prefix.as_str()).unwrap_or("");
+            let prefix = format!("\n{}", prefix.trim_start_matches('\n'));
+            table.decor_mut().set_prefix(prefix);
+        }
+    }
+
+    let current = parents.clone();
+    table.sort_values_by(|a, _, b, _| compare_keys(&current, a.get(), b.get()));
+
+    let mut keys: Vec<String> = table.iter().map(|(key, _)| key.to_string()).collect();
+    keys.sort_by(|a, b| compare_keys(&current, a, b));
+
+    for key in keys {
+        parents.push(key.clone());
+        match table.get_mut(&key) {
+            Some(Item::Table(sub_table)) => normalize_toml_table(sub_table, parents, position),
+            // Tables of an array, such as rewrite rules, have the keys of the array as parents
+            Some(Item::ArrayOfTables(array)) => {
+                for sub_table in array.iter_mut() {
+                    normalize_toml_table(sub_table, parents, position);
+                }
+            }
+            Some(Item::Value(toml_edit::Value::InlineTable(inline_table))) => {
+                let inline_parents = parents.clone();
+                inline_table.sort_values_by(|a, _, b, _| compare_keys(&inline_parents, a.get(), b.get()));
+                // Moved values keep their spacing, so space the table anew
+                inline_table.fmt();
+            }
+            _ => {}
+        }
+        parents.pop();
+    }
+}
+
+/// Line of a YAML file with the comment lines preceding it
+struct YamlLine {
+    indent: usize,
+    text: String,
+    comments: Vec<String>,
+}
+
+/// Mapping entry or list item of a YAML file
+struct YamlEntry {
+    comments: Vec<String>,
+    /// Unquoted key, empty for list items
+    key: String,
+    /// Entry text without indentation, including any trailing comment
+    text: String,
+    children: Vec<YamlEntry>,
+}
+
+/// Reformat a YAML configuration line by line so that comments are kept
+///
+/// Returns `None` for constructs other than block mappings and block lists of
+/// single-line values or mappings.
+fn normalize_yaml(content: &str) -> Option<String> {
+    let mut lines = Vec::new();
+    let mut comments = Vec::new();
+
+    for raw_line in content.lines() {
+        let text = raw_line.trim();
+        if text.is_empty() || (lines.is_empty() && comments.is_empty() && text == "---") {
+            continue;
+        }
+        if text.starts_with('#') {
+            comments.push(text.to_string());
+            continue;
+        }
+
+        let indentation = &raw_line[..raw_line.len() - raw_line.trim_start().len()];
+        if indentation.contains('\t') {
+            return None;
+        }
+        // A list item holding a mapping, such as `- from:`, is read as a `-` line with the mapping nested below
+        let item_value = text.strip_prefix("- ").map(str::trim_start).filter(|value| split_yaml_key(value).is_some());
+        let (text, nested) = match item_value {
+            Some(value) => ("-", Some((indentation.len() + text.len() - value.len(), value))),
+            None => (text, None),
+        };
+        lines.push(YamlLine {
+            indent: indentation.len(),
+            text: text.to_string(),
+            comments: std::mem::take(&mut comments),
+        });
+        if let Some((indent, value)) = nested {
+            lines.push(YamlLine {
+                indent,
+                text: value.to_string(),
+                comments: Vec::new(),
+            });
+        }
+    }
+
+    // Comments opening the file describe the whole file and stay on top
+    let mut output = String::new();
+    if let Some(first_line) = lines.first_mut() {
+        for comment in first_line.comments.drain(..) {
+            output.push_str(&comment);
+            output.push('\n');
+        }
+    }
+
+    let mut position = 0;
+    let entries = parse_yaml_block(&lines, &mut position, lines.first().map_or(0, |line| line.indent), false)?;
+    if position != lines.len() {
+        return None;
+    }
+
+    emit_yaml_entries(entries, &mut Vec::new(), 0, &mut output);
+    for comment in comments {
+        output.push_str(&comment);
+        output.push('\n');
+    }
+    Some(output)
+}
+
+/// Parse the entries of a YAML block at the given indentation
+fn parse_yaml_block(lines: &[YamlLine], position: &mut usize, indent: usize, items: bool) -> Option<Vec<YamlEntry>> {
+    let mut entries = Vec::new();
+
+    while let Some(line) = lines.get(*position) {
+        let is_item = line.text == "-" || line.text.starts_with("- ");
+        if line.indent < indent || (items && !is_item) {
+            break;
+        }
+        if line.indent > indent || is_item != items {
+            return None;
+        }
+        *position += 1;
+
+        let (key, value) = if is_item {
+            (String::new(), line.text[1..].trim())
+        } else {
+            split_yaml_key(&line.text)?
+        };
+        let value_is_empty = value.is_empty() || value.starts_with('#');
+        if !value_is_empty && value.starts_with(['|', '>', '&', '*', '!', '{', '[', '?']) {
+            return None;
+        }
+
+        let mut children = Vec::new();
+        if let Some(next) = lines.get(*position) {
+            let next_is_item = next.text == "-" || next.text.starts_with("- ");
+            let nested = next.indent > line.indent || (!is_item && next_is_item && next.indent == line.indent);
+            if nested {
+                // Items hold mappings, not nested lists
+                if !value_is_empty || (is_item && next_is_item) {
+                    return None;
+                }
+                children = parse_yaml_block(lines, position, next.indent, next_is_item)?;
+            }
+        }
+
+        entries.push(YamlEntry {
+            comments: line.comments.clone(),
+            key,
+            text: line.text.clone(),
+            children,
+        });
+    }
+
+    Some(entries)
+}
+
+/// Split a YAML mapping line into its unquoted key and the rest of the line
+fn split_yaml_key(text: &str) -> Option<(String, &str)> {
+    if let Some(quote) = text.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let end = text[1..].find(quote)? + 1;
+        let rest = text[end + 1..].strip_prefix(':')?;
+        if !(rest.is_empty() || rest.starts_with(' ')) {
+            return None;
+        }
+        return Some((text[1..end].to_string(), rest.trim()));
+    }
+
+    let colon = text
+        .char_indices()
+        .find(|(i, c)| *c == ':' && text[i + 1..].chars().next().is_none_or(|next| next == ' '))
+        .map(|(i, _)| i)?;
+    Some((text[..colon].trim().to_string(), text[colon + 1..].trim()))
+}
+
+/// Write YAML entries in `config fmt` order, indenting nested blocks by two spaces
+///
+/// A list item holding a mapping is written as `- ` followed by the first key of the mapping.
+fn emit_yaml_entries(mut entries: Vec<YamlEntry>, parents: &mut Vec<String>, indent: usize, output: &mut String) {
+    entries.sort_by(|a, b| {
+        if a.key.is_empty() || b.key.is_empty() {
+            Ordering::Equal
+        } else {
+            compare_keys(parents, &a.key, &b.key)
+        }
+    });
+
+    let padding = " ".repeat(indent);
+    for (i, entry) in entries.into_iter().enumerate() {
+        // Separate platforms by a blank line
+        if parents.is_empty() && i > 0 && !entry.children.is_empty() {
+            output.push('\n');
+        }
+        for comment in &entry.comments {
+            output.push_str(&format!("{}{}\n", padding, comment));
+        }
+
+        if entry.key.is_empty() && !entry.children.is_empty() {
+            // Items of a list have the keys of the list as parents, like arrays in `OrderedValue`
+            let mut mapping = String::new();
+            emit_yaml_entries(entry.children, parents, indent + 2, &mut mapping);
+            let mut first_key = true;
+            for line in mapping.lines() {
+                if first_key && !line.trim_start().starts_with('#') {
+                    output.push_str(&format!("{}- {}\n", padding, &line[indent + 2..]));
+                    first_key = false;
+                } else {
+                    output.push_str(&format!("{}\n", line));
+                }
+            }
+            continue;
+        }
+
+        output.push_str(&format!("{}{}\n", padding, entry.text));
+        parents.push(entry.key);
+        emit_yaml_entries(entry.children, parents, indent + 2, output);
+        parents.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Format a configuration, checking that formatting again changes nothing
+    fn normalize(format: ConfigFormat, content: &str) -> String {
+        let normalized = format.normalize(content, "test").unwrap();
+        assert_eq!(format.normalize(&normalized, "test").unwrap(), normalized, "not idempotent");
+        normalized
+    }
+
+    #[test]
+    fn json_is_ordered_without_changing_the_config() {
+        let content = r#"{"quake": {"rewrites": {"fofa": [{"to": {"field": "protocol", "value": "https"}, "from": {"field": "protocol"}}]},
+            "fields": {"title": {"aliases": ["t"], "name": "title"}}}, "fofa": {"operators": {"or": "||", "and": "&&"}}, "extends": "base.json"}"#;
+        assert_eq!(
+            normalize(ConfigFormat::Json, content),
+            r#"{
+  "extends": "base.json",
+  "fofa": {
+    "operators": {
+      "and": "&&",
+      "or": "||"
+    }
+  },
+  "quake": {
+    "fields": {
+      "title": {
+        "name": "title",
+        "aliases": [
+          "t"
+        ]
+      }
+    },
+    "rewrites": {
+      "fofa": [
+        {
+          "from": {
+            "field": "protocol"
+          },
+          "to": {
+            "field": "protocol",
+            "value": "https"
+          }
+        }
+      ]
+    }
+  }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn toml_keeps_comments_while_ordering() {
+        let content = r#"# User overrides
+[quake.fields.title]
+aliases = ["t"] # short form
+name = "title"
+
+[[quake.rewrites.fofa]]
+to = { value = "https", field = "protocol" }
+from = { field = "protocol", value = "ssl/http" }
+
+# Platform operators
+[fofa.operators]
+or = "||"
+and = "&&"
+"#;
+        let normalized = normalize(ConfigFormat::Toml, content);
+        assert_eq!(
+            normalized,
+            r#"# Platform operators
+[fofa.operators]
+and = "&&"
+or = "||"
+
+# User overrides
+[quake.fields.title]
+name = "title"
+aliases = ["t"] # short form
+
+[[quake.rewrites.fofa]]
+from = { field = "protocol", value = "ssl/http" }
+to = { field = "protocol", value = "https" }
+"#
+        );
+    }
+
+    #[test]
+    fn yaml_keeps_comments_in_lists_of_mappings() {
+        let content = r#"# User overrides
+quake:
+  rewrites:
+    fofa:
+      # TLS services
+      - to:
+          value: https
+          field: protocol
+        from:
+          field: protocol   # service name
+          value: ssl/http
+        tests:
+          - expected: protocol="https"
+            query: service:"ssl/http"
+  fields:
+    title:
+      aliases:
+        - t
+      name: title
+fofa:
+  operators:
+    or: "||"
+    and: "&&"
+"#;
+        assert_eq!(
+            normalize(ConfigFormat::Yaml, content),
+            r#"# User overrides
+fofa:
+  operators:
+    and: "&&"
+    or: "||"
+
+quake:
+  fields:
+    title:
+      name: title
+      aliases:
+        - t
+  rewrites:
+    fofa:
+      # TLS services
+      - from:
+          field: protocol   # service name
+          value: ssl/http
+        to:
+          field: protocol
+          value: https
+        tests:
+          - query: service:"ssl/http"
+            expected: protocol="https"
+"#
+        );
+    }
+
+    #[test]
+    fn yaml_the_line_formatter_cannot_keep_comments_for_is_rejected() {
+        let content = "# Flow style\nfofa: {operators: {and: \"&&\"}}\n";
+        assert!(matches!(
+            ConfigFormat::Yaml.normalize(content, "test"),
+            Err(ConversionError::ConfigurationError(message)) if message.contains("without losing comments")
+        ));
+        assert_eq!(
+            normalize(ConfigFormat::Yaml, "fofa: {operators: {or: \"||\", and: \"&&\"}}\n"),
+            "fofa:\n  operators:\n    and: '&&'\n    or: '||'\n"
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use crate::config::{ConfigManager, ValuePattern};
+
+/// JSON Schema describing the configuration file format
+pub const CONFIG_SCHEMA: &str = include_str!("../../schema/config.schema.json");
+
+/// Field names the query validator can recognize: word characters separated by dots
+const FIELD_NAME_PATTERN: &str = r"^[A-Za-z0-9_]+(?:\.[A-Za-z0-9_]+)*$";
+
+/// Characters that delimit values in a query and cannot appear in an operator
+const RESERVED_OPERATOR_CHARS: &[char] = &['"', '\''];
+
+/// How serious a configuration issue is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    /// The configuration cannot be used and is rejected when loaded
+    Error,
+    /// The configuration loads but likely does not behave as intended
+    Warning,
+}
+
+/// Problem found in the configuration
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigIssue {
+    pub severity: IssueSeverity,
+    /// `/`-separated location such as `quake/operators/or`
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            IssueSeverity::Error => "error",
+            IssueSeverity::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", severity, self.location, self.message)
+    }
+}
+
+/// Checks a loaded configuration for mistakes serde cannot detect
+pub struct ConfigLinter;
+
+impl ConfigLinter {
+    /// Collect all issues of a configuration, errors first
+    pub fn lint(config_manager: &ConfigManager) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        let field_name_re = Regex::new(FIELD_NAME_PATTERN).unwrap();
+        let platforms = config_manager.get_supported_platforms();
+
+        for platform in &platforms {
+            let Ok(config) = config_manager.get_platform_config(platform) else {
+                continue;
+            };
+
+            let mut operators = vec![
+                ("equal", &config.operators.equal),
+                ("and", &config.operators.and),
+                ("or", &config.operators.or),
+                ("not_equal", &config.operators.not_equal),
+                ("left_paren", &config.operators.left_paren),
+                ("right_paren", &config.operators.right_paren),
+            ];
+            if let Some(exact) = &config.operators.exact {
+                operators.push(("exact", exact));
+            }
+            for (name, operator) in operators {
+                let location = format!("{}/operators/{}", platform, name);
+                if operator.trim().is_empty() {
+                    issues.push(Self::error(location, "Operator is empty".to_string()));
+                } else if operator.trim() != operator.as_str() {
+                    issues.push(Self::error(
+                        location,
+                        format!("Operator '{}' has leading or trailing whitespace", operator),
+                    ));
+                } else if operator.contains(RESERVED_OPERATOR_CHARS) {
+                    issues.push(Self::error(
+                        location,
+                        format!("Operator '{}' contains a quote, which delimits values in queries", operator),
+                    ));
+                }
+            }
+
+            let mut fields: Vec<_> = config.fields.iter().collect();
+            fields.sort_by_key(|(canonical, _)| *canonical);
+
+            // Native names and aliases of each canonical field, to find names shared by several fields
+            let mut canonical_by_name: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+            for (canonical, spec) in fields {
+                let location = format!("{}/fields/{}", platform, canonical);
+                Self::check_name(&field_name_re, "Field name", canonical, &location, &mut issues);
+                Self::check_name(&field_name_re, "Native name", &spec.name, &location, &mut issues);
+                for alias in &spec.aliases {
+                    Self::check_name(&field_name_re, "Alias", alias, &location, &mut issues);
+                }
+
+                for example in spec.examples.iter().filter(|example| !spec.field_type.accepts(example)) {
+                    issues.push(Self::warning(
+                        location.clone(),
+                        format!("Example '{}' is not a valid {} value", example, spec.field_type),
+                    ));
+                }
+
+                for (target, approximation) in &spec.approximations {
+                    let target_config = config_manager.get_platform_config(target);
+                    let message = match target_config {
+                        Err(_) => format!("Approximation for unknown platform '{}'", target),
+                        Ok(target_config) if !target_config.fields.contains_key(&approximation.field) => {
+                            format!("Approximation field '{}' is not defined on {}", approximation.field, target)
+                        }
+                        Ok(_) => continue,
+                    };
+                    issues.push(Self::warning(format!("{}/approximations/{}", location, target), message));
+                }
+
+                canonical_by_name.entry(&spec.name).or_default().push(canonical);
+                for alias in &spec.aliases {
+                    canonical_by_name.entry(alias).or_default().push(canonical);
+                }
+            }
+
+            for (target, rules) in &config.rewrites {
+                let target_config = config_manager.get_platform_config(target).ok();
+                for (i, rule) in rules.iter().enumerate() {
+                    let location = format!("{}/rewrites/{}/{}", platform, target, i);
+                    if !config.fields.contains_key(&rule.from.field) {
+                        issues.push(Self::error(
+                            location.clone(),
+                            format!("Field '{}' is not defined on {}", rule.from.field, platform),
+                        ));
+                    }
+                    match target_config {
+                        None => issues.push(Self::error(
+                            location.clone(),
+                            format!("Rewrite rules for unknown platform '{}'", target),
+                        )),
+                        Some(target_config) if !target_config.fields.contains_key(&rule.to.field) => {
+                            issues.push(Self::error(
+                                location.clone(),
+                                format!("Field '{}' is not defined on {}", rule.to.field, target),
+                            ))
+                        }
+                        Some(_) => {}
+                    }
+                    if let Err(e) = rule.value_regex() {
+                        // The last line of a regex error names the problem, the others draw the pattern
+                        let reason = e.to_string().lines().last().unwrap_or_default().trim_start_matches("error: ").to_string();
+                        issues.push(Self::error(
+                            location.clone(),
+                            format!("Invalid value pattern '{}': {}", rule.from.value.as_ref().map(ValuePattern::as_str).unwrap_or_default(), reason),
+                        ));
+                    }
+                    if rule.tests.is_empty() {
+                        issues.push(Self::warning(location, "Rewrite rule has no tests".to_string()));
+                    }
+                }
+            }
+
+            for (name, mut canonicals) in canonical_by_name {
+                canonicals.dedup();
+                if canonicals.len() > 1 {
+                    issues.push(Self::warning(
+                        format!("{}/fields", platform),
+                        format!(
+                            "Name '{}' is used by several fields ({}), so it converts back to only one of them",
+                            name,
+                            canonicals.join(", ")
+                        ),
+                    ));
+                }
+            }
+        }
+
+        // Fields every platform is expected to define; platform-only fields are not
+        let all_fields: BTreeSet<&String> = platforms
+            .iter()
+            .filter_map(|platform| config_manager.get_platform_config(platform).ok())
+            .flat_map(|config| config.fields.iter())
+            .filter(|(_, spec)| !spec.platform_only)
+            .map(|(field, _)| field)
+            .collect();
+        for field in all_fields {
+            // A platform other platforms approximate the field on is not missing it
+            let approximated: BTreeSet<&String> = platforms
+                .iter()
+                .filter_map(|platform| config_manager.get_platform_config(platform).ok())
+                .filter_map(|config| config.fields.get(field))
+                .flat_map(|spec| spec.approximations.keys())
+                .collect();
+            let missing: Vec<&str> = platforms
+                .iter()
+                .filter(|platform| !approximated.contains(platform))
+                .filter(|platform| {
+                    config_manager
+                        .get_platform_config(platform)
+                        .map(|config| !config.fields.contains_key(field))
+                        .unwrap_or(false)
+                })
+                .map(String::as_str)
+                .collect();
+            if !missing.is_empty() {
+                issues.push(Self::warning(
+                    format!("*/fields/{}", field),
+                    format!("Field is not defined on {}", missing.join(", ")),
+                ));
+            }
+        }
+
+        issues.sort_by_key(|issue| issue.severity);
+        issues
+    }
+
+    /// Check that a field name is usable in queries
+    fn check_name(field_name_re: &Regex, kind: &str, name: &str, location: &str, issues: &mut Vec<ConfigIssue>) {
+        if name.trim() != name {
+            issues.push(Self::error(
+                location.to_string(),
+                format!("{} '{}' has leading or trailing whitespace", kind, name),
+            ));
+        } else if !field_name_re.is_match(name) {
+            issues.push(Self::error(
+                location.to_string(),
+                format!("{} '{}' may only contain letters, digits, '_' and '.'", kind, name),
+            ));
+        }
+    }
+
+    /// Create an error issue
+    fn error(location: String, message: String) -> ConfigIssue {
+        ConfigIssue {
+            severity: IssueSeverity::Error,
+            location,
+            message,
+        }
+    }
+
+    /// Create a warning issue
+    fn warning(location: String, message: String) -> ConfigIssue {
+        ConfigIssue {
+            severity: IssueSeverity::Warning,
+            location,
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+    use serde_json::{json, Value};
+
+    /// Lint the embedded configuration after an edit, as `(severity, location, message)` tuples
+    fn lint_with(edit: impl FnOnce(&mut Value)) -> Vec<(IssueSeverity, String, String)> {
+        let mut config: Value = serde_json::from_str(EMBEDDED_CONFIG).unwrap();
+        edit(&mut config);
+        let config_manager = ConfigManager::from_value_unchecked(config, vec![EMBEDDED_CONFIG_SOURCE.to_string()]).unwrap();
+        ConfigLinter::lint(&config_manager)
+            .into_iter()
+            .map(|issue| (issue.severity, issue.location, issue.message))
+            .collect()
+    }
+
+    fn has_issue(issues: &[(IssueSeverity, String, String)], severity: IssueSeverity, location: &str, message: &str) -> bool {
+        issues.iter().any(|issue| issue.0 == severity && issue.1 == location && issue.2.contains(message))
+    }
+
+    #[test]
+    fn embedded_config_has_no_issues() {
+        assert_eq!(lint_with(|_| {}), []);
+    }
+
+    #[test]
+    fn bad_operators_are_errors() {
+        let issues = lint_with(|config| {
+            config["fofa"]["operators"]["and"] = json!(" ");
+            config["fofa"]["operators"]["or"] = json!(" || ");
+            config["fofa"]["operators"]["equal"] = json!("='");
+        });
+        assert!(has_issue(&issues, IssueSeverity::Error, "fofa/operators/and", "Operator is empty"));
+        assert!(has_issue(&issues, IssueSeverity::Error, "fofa/operators/or", "leading or trailing whitespace"));
+        assert!(has_issue(&issues, IssueSeverity::Error, "fofa/operators/equal", "contains a quote"));
+    }
+
+    #[test]
+    fn names_unusable_in_queries_are_errors() {
+        let issues = lint_with(|config| {
+            config["fofa"]["fields"]["title"] = json!({"name": "web title", "aliases": [" t"]});
+        });
+        assert!(has_issue(&issues, IssueSeverity::Error, "fofa/fields/title", "Native name 'web title' may only contain"));
+        assert!(has_issue(&issues, IssueSeverity::Error, "fofa/fields/title", "Alias ' t' has leading or trailing whitespace"));
+    }
+
+    #[test]
+    fn invalid_examples_and_approximations_are_warnings() {
+        let issues = lint_with(|config| {
+            config["fofa"]["fields"]["port"] = json!({"name": "port", "type": "number", "examples": ["http"]});
+            config["fofa"]["fields"]["host"]["approximations"] = json!({"nowhere": {"field": "domain"}, "quake": {"field": "missing"}});
+        });
+        assert!(has_issue(&issues, IssueSeverity::Warning, "fofa/fields/port", "Example 'http' is not a valid number value"));
+        assert!(has_issue(&issues, IssueSeverity::Warning, "fofa/fields/host/approximations/nowhere", "unknown platform 'nowhere'"));
+        assert!(has_issue(&issues, IssueSeverity::Warning, "fofa/fields/host/approximations/quake", "'missing' is not defined on quake"));
+    }
+
+    #[test]
+    fn broken_rewrite_rules_are_reported() {
+        let issues = lint_with(|config| {
+            config["fofa"]["rewrites"] = json!({
+                "quake": [
+                    {"from": {"field": "missing", "value": "("}, "to": {"field": "absent"}},
+                ],
+                "nowhere": [
+                    {"from": {"field": "title"}, "to": {"field": "title"}, "tests": [{"query": "title=\"a\"", "expected": "title=\"a\""}]},
+                ],
+            });
+        });
+        assert!(has_issue(&issues, IssueSeverity::Error, "fofa/rewrites/quake/0", "Field 'missing' is not defined on fofa"));
+        assert!(has_issue(&issues, IssueSeverity::Error, "fofa/rewrites/quake/0", "Field 'absent' is not defined on quake"));
+        assert!(has_issue(&issues, IssueSeverity::Error, "fofa/rewrites/quake/0", "Invalid value pattern '('"));
+        assert!(has_issue(&issues, IssueSeverity::Warning, "fofa/rewrites/quake/0", "Rewrite rule has no tests"));
+        assert!(has_issue(&issues, IssueSeverity::Error, "fofa/rewrites/nowhere/0", "unknown platform 'nowhere'"));
+        assert_eq!(issues[0].0, IssueSeverity::Error);
+    }
+
+    #[test]
+    fn shared_names_and_missing_fields_are_warnings() {
+        let issues = lint_with(|config| {
+            config["hunter"]["fields"]["host"] = json!("domain");
+        });
+        assert!(has_issue(&issues, IssueSeverity::Warning, "hunter/fields", "Name 'domain' is used by several fields (domain, host)"));
+
+        let issues = lint_with(|config| {
+            config["quake"]["fields"].as_object_mut().unwrap().remove("title");
+        });
+        assert!(has_issue(&issues, IssueSeverity::Warning, "*/fields/title", "Field is not defined on quake"));
+    }
+
+    #[test]
+    fn approximated_fields_are_not_missing() {
+        let issues = lint_with(|config| {
+            for platform in ["fofa", "quake", "zoomeye", "threatbook"] {
+                config[platform]["fields"]["host"]["approximations"] = json!({});
+            }
+        });
+        assert!(has_issue(&issues, IssueSeverity::Warning, "*/fields/host", "Field is not defined on hunter"));
+    }
+}
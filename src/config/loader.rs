@@ -0,0 +1,386 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::config::{ConfigFormat, ConfigManager};
+use crate::error::{ConversionError, ConversionResult};
+
+/// Configuration shipped with ConvertiX, compiled into the binary
+pub const EMBEDDED_CONFIG: &str = include_str!("../../config.json");
+
+/// Source name reported for the embedded configuration
+pub const EMBEDDED_CONFIG_SOURCE: &str = "<embedded>";
+
+/// Environment variable naming a user configuration file
+pub const CONFIG_ENV_VAR: &str = "CONVERTIX_CONFIG";
+
+/// Top-level key listing configuration files a file builds upon
+pub const EXTENDS_KEY: &str = "extends";
+
+/// Top-level key pointing editors at the configuration JSON Schema
+pub const SCHEMA_KEY: &str = "$schema";
+
+/// Configuration merged from several layers, with the origin of every value
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedConfig {
+    /// Effective merged configuration
+    pub config: Value,
+    /// Source of each value, keyed by `/`-separated path such as `fofa/fields/title`
+    pub origins: BTreeMap<String, String>,
+    /// Sources in the order they were merged, lowest precedence first
+    pub sources: Vec<String>,
+}
+
+/// Locates, layers and loads the effective configuration
+pub struct ConfigLoader;
+
+impl ConfigLoader {
+    /// Load the embedded configuration overlaid with the user configuration layers
+    pub fn load(config_paths: &[PathBuf]) -> ConversionResult<ConfigManager> {
+        let resolved = Self::resolve(config_paths)?;
+        ConfigManager::from_value(resolved.config, resolved.sources)
+    }
+
+    /// Load the configuration like [`ConfigLoader::load`] without rejecting lint errors
+    pub fn load_unchecked(config_paths: &[PathBuf]) -> ConversionResult<ConfigManager> {
+        let resolved = Self::resolve(config_paths)?;
+        ConfigManager::from_value_unchecked(resolved.config, resolved.sources)
+    }
+
+    /// Merge the embedded configuration with the user configuration layers
+    ///
+    /// The user layers are the given files in order, or when none are given,
+    /// `$CONVERTIX_CONFIG` or `convertix/config.{json,toml,yaml,yml}` in the
+    /// XDG config directories. A file's `extends` entries are merged before the file
+    /// itself. Later layers add platforms and add or override individual
+    /// fields and operators of earlier ones.
+    pub fn resolve(config_paths: &[PathBuf]) -> ConversionResult<ResolvedConfig> {
+        let mut resolved = ResolvedConfig {
+            config: Value::Object(Map::new()),
+            origins: BTreeMap::new(),
+            sources: Vec::new(),
+        };
+
+        let embedded: Value = serde_json::from_str(EMBEDDED_CONFIG).map_err(|e| {
+            ConversionError::ConfigurationError(format!("Failed to parse embedded config: {}", e))
+        })?;
+        resolved.merge_layer(embedded, EMBEDDED_CONFIG_SOURCE);
+
+        let user_paths = if config_paths.is_empty() {
+            Self::discover()?.into_iter().collect()
+        } else {
+            config_paths.to_vec()
+        };
+
+        for path in user_paths {
+            Self::merge_file(&mut resolved, &path, &mut Vec::new())?;
+        }
+
+        Ok(resolved)
+    }
+
+    /// Find the user configuration file used when no `--config` is given
+    ///
+    /// `$CONVERTIX_CONFIG` must exist when set; XDG locations are only used
+    /// when present.
+    pub fn discover() -> ConversionResult<Option<PathBuf>> {
+        if let Some(path) = env::var_os(CONFIG_ENV_VAR).filter(|path| !path.is_empty()) {
+            let path = PathBuf::from(path);
+            if !path.is_file() {
+                return Err(ConversionError::ConfigurationError(format!(
+                    "Config file '{}' from {} does not exist",
+                    path.display(),
+                    CONFIG_ENV_VAR
+                )));
+            }
+            return Ok(Some(path));
+        }
+
+        Ok(Self::xdg_config_paths().into_iter().find(|path| path.is_file()))
+    }
+
+    /// Merge a configuration file after the files it extends
+    fn merge_file(resolved: &mut ResolvedConfig, path: &Path, chain: &mut Vec<PathBuf>) -> ConversionResult<()> {
+        let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if let Some(start) = chain.iter().position(|file| *file == canonical_path) {
+            let cycle: Vec<String> = chain[start..]
+                .iter()
+                .chain([&canonical_path])
+                .map(|file| format!("'{}'", file.display()))
+                .collect();
+            return Err(ConversionError::ConfigurationError(format!(
+                "Config files extend each other in a cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        let mut value = Self::read_layer(path)?;
+        if let Some(object) = value.as_object_mut() {
+            object.remove(SCHEMA_KEY);
+        }
+
+        let extends = match value.as_object_mut().and_then(|object| object.remove(EXTENDS_KEY)) {
+            Some(Value::String(parent)) => vec![parent],
+            Some(Value::Array(parents)) => parents
+                .into_iter()
+                .map(|parent| match parent {
+                    Value::String(parent) => Ok(parent),
+                    _ => Err(Self::invalid_extends(path)),
+                })
+                .collect::<ConversionResult<_>>()?,
+            Some(_) => return Err(Self::invalid_extends(path)),
+            None => Vec::new(),
+        };
+
+        chain.push(canonical_path);
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for parent in extends {
+            Self::merge_file(resolved, &base_dir.join(parent), chain)?;
+        }
+        chain.pop();
+
+        resolved.merge_layer(value, &path.display().to_string());
+        Ok(())
+    }
+
+    /// Read a configuration file into a JSON value, parsed according to its extension
+    fn read_layer(path: &Path) -> ConversionResult<Value> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            ConversionError::ConfigurationError(format!("Failed to read config file '{}': {}", path.display(), e))
+        })?;
+
+        ConfigFormat::from_path(path).parse(&content, &path.display().to_string())
+    }
+
+    /// Error for an `extends` entry that is not a path or list of paths
+    fn invalid_extends(path: &Path) -> ConversionError {
+        ConversionError::ConfigurationError(format!(
+            "'{}' in config file '{}' must be a path or a list of paths",
+            EXTENDS_KEY,
+            path.display()
+        ))
+    }
+
+    /// Candidate config files in the XDG config directories, highest precedence first
+    fn xdg_config_paths() -> Vec<PathBuf> {
+        let mut config_dirs = Vec::new();
+
+        match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => config_dirs.push(PathBuf::from(dir)),
+            None => {
+                if let Some(home) = env::var_os("HOME") {
+                    config_dirs.push(PathBuf::from(home).join(".config"));
+                }
+            }
+        }
+
+        let system_dirs = env::var("XDG_CONFIG_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/etc/xdg".to_string());
+        config_dirs.extend(system_dirs.split(':').filter(|dir| !dir.is_empty()).map(PathBuf::from));
+
+        config_dirs
+            .into_iter()
+            .flat_map(|dir| {
+                ConfigFormat::EXTENSIONS
+                    .iter()
+                    .map(move |extension| dir.join("convertix").join(format!("config.{}", extension)))
+            })
+            .collect()
+    }
+}
+
+impl ResolvedConfig {
+    /// Deep-merge a layer on top of the configuration merged so far
+    fn merge_layer(&mut self, layer: Value, source: &str) {
+        merge_value(&mut self.config, layer, "", source, &mut self.origins);
+        self.sources.push(source.to_string());
+    }
+}
+
+/// Merge objects key by key; any other value replaces the existing one
+fn merge_value(target: &mut Value, layer: Value, path: &str, source: &str, origins: &mut BTreeMap<String, String>) {
+    // Metadata layered onto a plain-string field applies to the field of that native name
+    if let (Value::String(name), Value::Object(_)) = (&*target, &layer) {
+        if is_field_path(path) {
+            if let Some(name_source) = origins.remove(path) {
+                origins.insert(format!("{}/name", path), name_source);
+            }
+            *target = serde_json::json!({ "name": name });
+        }
+    }
+
+    match (target, layer) {
+        (Value::Object(target_map), Value::Object(layer_map)) => {
+            for (key, layer_value) in layer_map {
+                let child_path = if path.is_empty() { key.clone() } else { format!("{}/{}", path, key) };
+                match target_map.get_mut(&key) {
+                    Some(target_value) => merge_value(target_value, layer_value, &child_path, source, origins),
+                    None => {
+                        record_origins(&layer_value, &child_path, source, origins);
+                        target_map.insert(key, layer_value);
+                    }
+                }
+            }
+        }
+        (target, layer) => {
+            // Drop origins of values nested under the replaced value
+            let prefix = format!("{}/", path);
+            origins.retain(|origin_path, _| !origin_path.starts_with(&prefix));
+            record_origins(&layer, path, source, origins);
+            *target = layer;
+        }
+    }
+}
+
+/// Check whether a path such as `fofa/fields/title` names a field entry
+fn is_field_path(path: &str) -> bool {
+    let parts: Vec<&str> = path.split('/').collect();
+    parts.len() == 3 && parts[1] == "fields"
+}
+
+/// Record the source of every leaf value below a path
+fn record_origins(value: &Value, path: &str, source: &str, origins: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                record_origins(child, &format!("{}/{}", path, key), source, origins);
+            }
+        }
+        _ => {
+            origins.insert(path.to_string(), source.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn extends_cycle_reports_the_chain() {
+        let dir = std::env::temp_dir().join(format!("convertix-cycle-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.json"), r#"{"extends": "b.json"}"#).unwrap();
+        fs::write(dir.join("b.json"), r#"{"extends": "a.json"}"#).unwrap();
+
+        let a = dir.join("a.json").canonicalize().unwrap();
+        let error = ConfigLoader::resolve(&[dir.join("a.json")]).unwrap_err().to_string();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(error.contains("cycle"), "{}", error);
+        assert!(error.contains("b.json' -> '"), "{}", error);
+        assert!(error.ends_with(&format!("'{}'", a.display())), "{}", error);
+    }
+
+    fn layered(layers: &[(&str, Value)]) -> ResolvedConfig {
+        let mut resolved = ResolvedConfig { config: Value::Object(Map::new()), origins: BTreeMap::new(), sources: Vec::new() };
+        for (source, layer) in layers {
+            resolved.merge_layer(layer.clone(), source);
+        }
+        resolved
+    }
+
+    #[test]
+    fn later_layers_override_leaves_and_keep_the_rest() {
+        let resolved = layered(&[
+            ("base", json!({"fofa": {"operators": {"and": "&&", "or": "||"}, "fields": {"title": "title"}}})),
+            ("user", json!({"fofa": {"operators": {"and": "AND"}}, "quake": {"fields": {}}})),
+        ]);
+
+        assert_eq!(resolved.config["fofa"]["operators"], json!({"and": "AND", "or": "||"}));
+        assert_eq!(resolved.config["fofa"]["fields"]["title"], "title");
+        assert_eq!(resolved.origins["fofa/operators/and"], "user");
+        assert_eq!(resolved.origins["fofa/operators/or"], "base");
+        assert_eq!(resolved.origins["quake/fields"], "user");
+        assert_eq!(resolved.sources, ["base", "user"]);
+    }
+
+    #[test]
+    fn arrays_are_replaced_with_the_origins_below_them() {
+        let resolved = layered(&[
+            ("base", json!({"fofa": {"fields": {"title": {"name": "title", "aliases": ["t", "head"]}}}})),
+            ("user", json!({"fofa": {"fields": {"title": {"aliases": ["caption"]}}, "rewrites": {"quake": [{"pattern": "a"}]}}})),
+            ("last", json!({"fofa": {"rewrites": "none"}})),
+        ]);
+
+        assert_eq!(resolved.config["fofa"]["fields"]["title"]["aliases"], json!(["caption"]));
+        assert_eq!(resolved.origins["fofa/fields/title/aliases"], "user");
+        assert_eq!(resolved.origins["fofa/fields/title/name"], "base");
+        assert_eq!(resolved.origins["fofa/rewrites"], "last");
+        assert!(!resolved.origins.keys().any(|path| path.starts_with("fofa/rewrites/")));
+    }
+
+    #[test]
+    fn metadata_on_a_plain_field_keeps_its_name() {
+        let resolved = layered(&[
+            ("base", json!({"fofa": {"fields": {"title": "title"}}})),
+            ("user", json!({"fofa": {"fields": {"title": {"description": "Page title"}}}})),
+        ]);
+
+        assert_eq!(resolved.config["fofa"]["fields"]["title"], json!({"name": "title", "description": "Page title"}));
+        assert_eq!(resolved.origins["fofa/fields/title/name"], "base");
+        assert_eq!(resolved.origins["fofa/fields/title/description"], "user");
+        assert!(!resolved.origins.contains_key("fofa/fields/title"));
+    }
+
+    #[test]
+    fn files_merge_after_what_they_extend_and_in_the_given_order() {
+        let dir = std::env::temp_dir().join(format!("convertix-layers-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("base.json"), r#"{"fofa": {"operators": {"and": "base", "or": "base"}}}"#).unwrap();
+        fs::write(dir.join("team.json"), r#"{"extends": "base.json", "fofa": {"operators": {"and": "team", "or": "team"}}}"#).unwrap();
+        fs::write(dir.join("user.toml"), "[fofa.operators]\nor = \"user\"\n").unwrap();
+
+        let resolved = ConfigLoader::resolve(&[dir.join("team.json"), dir.join("user.toml")]);
+        fs::remove_dir_all(&dir).unwrap();
+        let resolved = resolved.unwrap();
+
+        let sources: Vec<_> = resolved.sources.iter().map(|source| source.rsplit('/').next().unwrap()).collect();
+        assert_eq!(sources, [EMBEDDED_CONFIG_SOURCE, "base.json", "team.json", "user.toml"]);
+        assert_eq!(resolved.config["fofa"]["operators"]["and"], "team");
+        assert_eq!(resolved.config["fofa"]["operators"]["or"], "user");
+        assert!(resolved.origins["fofa/operators/or"].ends_with("user.toml"));
+    }
+
+    #[test]
+    fn discover_prefers_the_env_var_then_the_xdg_directories() {
+        // The only test touching these variables, so it cannot race with others
+        let dir = std::env::temp_dir().join(format!("convertix-discover-{}", std::process::id()));
+        let home_config = dir.join("home").join("convertix").join("config.toml");
+        let system_config = dir.join("system").join("convertix").join("config.json");
+        for path in [&home_config, &system_config] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let saved: Vec<_> = [CONFIG_ENV_VAR, "XDG_CONFIG_HOME", "XDG_CONFIG_DIRS"].map(|name| (name, env::var_os(name))).into();
+
+        env::set_var("XDG_CONFIG_HOME", dir.join("home"));
+        env::set_var("XDG_CONFIG_DIRS", format!("{}:/nonexistent", dir.join("system").display()));
+        env::set_var(CONFIG_ENV_VAR, &system_config);
+        let from_env = ConfigLoader::discover();
+        env::set_var(CONFIG_ENV_VAR, dir.join("missing.json"));
+        let missing = ConfigLoader::discover();
+        env::remove_var(CONFIG_ENV_VAR);
+        let from_home = ConfigLoader::discover();
+        fs::remove_file(&home_config).unwrap();
+        let from_system = ConfigLoader::discover();
+
+        for (name, value) in saved {
+            match value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(from_env.unwrap(), Some(system_config.clone()));
+        assert!(matches!(missing, Err(ConversionError::ConfigurationError(message)) if message.contains(CONFIG_ENV_VAR)));
+        assert_eq!(from_home.unwrap(), Some(home_config));
+        assert_eq!(from_system.unwrap(), Some(system_config));
+    }
+}
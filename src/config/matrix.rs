@@ -0,0 +1,148 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use crate::config::{ConfigManager, FieldSpec};
+use crate::error::ConversionResult;
+
+/// Canonical field names crossed with the native field names of each platform
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldMatrix {
+    pub platforms: Vec<String>,
+    pub fields: Vec<FieldMatrixRow>,
+}
+
+/// Native names of one canonical field, `None` where a platform has no equivalent
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldMatrixRow {
+    pub field: String,
+    pub platforms: BTreeMap<String, Option<String>>,
+}
+
+impl FieldMatrix {
+    /// Build the matrix for the given platforms (all platforms when empty)
+    pub fn build(config_manager: &ConfigManager, platforms: &[String]) -> ConversionResult<Self> {
+        let platforms = if platforms.is_empty() {
+            config_manager.get_supported_platforms()
+        } else {
+            platforms.to_vec()
+        };
+
+        let mut platform_configs = Vec::new();
+        for platform in &platforms {
+            platform_configs.push((platform, config_manager.get_platform_config(platform)?));
+        }
+
+        // Collect the union of canonical field names across the selected platforms
+        let field_names: BTreeSet<&String> = platform_configs
+            .iter()
+            .flat_map(|(_, config)| config.fields.keys())
+            .collect();
+
+        let fields = field_names
+            .into_iter()
+            .map(|field_name| FieldMatrixRow {
+                field: field_name.clone(),
+                platforms: platform_configs
+                    .iter()
+                    .map(|(platform, config)| ((*platform).clone(), config.native_name(field_name).map(String::from)))
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Self { platforms, fields })
+    }
+}
+
+/// Metadata of canonical fields on each platform, for `fields --describe`
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldCatalog {
+    pub platforms: Vec<String>,
+    pub fields: Vec<FieldCatalogEntry>,
+}
+
+/// Metadata of one canonical field on the platforms that define it
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldCatalogEntry {
+    pub field: String,
+    pub platforms: BTreeMap<String, FieldSpec>,
+}
+
+impl FieldCatalog {
+    /// Build the catalog for the given platforms and canonical fields (all when empty)
+    pub fn build(config_manager: &ConfigManager, platforms: &[String], fields: &[String]) -> ConversionResult<Self> {
+        let matrix = FieldMatrix::build(config_manager, platforms)?;
+
+        let mut entries = Vec::new();
+        for row in matrix.fields {
+            if !fields.is_empty() && !fields.contains(&row.field) {
+                continue;
+            }
+
+            let mut specs = BTreeMap::new();
+            for platform in &matrix.platforms {
+                if let Some(spec) = config_manager.get_platform_config(platform)?.fields.get(&row.field) {
+                    specs.insert(platform.clone(), spec.clone());
+                }
+            }
+            entries.push(FieldCatalogEntry {
+                field: row.field,
+                platforms: specs,
+            });
+        }
+
+        Ok(Self {
+            platforms: matrix.platforms,
+            fields: entries,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+    use crate::error::ConversionError;
+
+    fn manager() -> ConfigManager {
+        ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap()
+    }
+
+    #[test]
+    fn matrix_crosses_fields_with_native_names() {
+        let matrix = FieldMatrix::build(&manager(), &[]).unwrap();
+        assert_eq!(matrix.platforms, manager().get_supported_platforms());
+
+        let title = matrix.fields.iter().find(|row| row.field == "title").unwrap();
+        assert_eq!(title.platforms["fofa"].as_deref(), Some("title"));
+        assert_eq!(title.platforms["hunter"].as_deref(), Some("web.title"));
+
+        // Platform-only fields have no name on the other platforms
+        let is_web = matrix.fields.iter().find(|row| row.field == "is_web").unwrap();
+        assert_eq!(is_web.platforms["hunter"].as_deref(), Some("is_web"));
+        assert_eq!(is_web.platforms["fofa"], None);
+    }
+
+    #[test]
+    fn matrix_only_holds_the_selected_platforms() {
+        let platforms = ["quake".to_string(), "fofa".to_string()];
+        let matrix = FieldMatrix::build(&manager(), &platforms).unwrap();
+        assert_eq!(matrix.platforms, platforms);
+        assert!(matrix.fields.iter().all(|row| row.platforms.keys().eq(["fofa", "quake"])));
+        // Fields of the other platforms only are left out
+        assert!(!matrix.fields.iter().any(|row| row.field == "is_web"));
+
+        assert!(matches!(
+            FieldMatrix::build(&manager(), &["nope".to_string()]),
+            Err(ConversionError::UnsupportedPlatform(_))
+        ));
+    }
+
+    #[test]
+    fn catalog_holds_the_specs_of_the_selected_fields() {
+        let catalog = FieldCatalog::build(&manager(), &[], &["port".to_string()]).unwrap();
+        assert_eq!(catalog.fields.len(), 1);
+        let port = &catalog.fields[0];
+        assert_eq!(port.field, "port");
+        assert_eq!(port.platforms["hunter"].name, "ip.port");
+        assert_eq!(port.platforms.len(), catalog.platforms.len());
+    }
+}
@@ -1,3 +1,17 @@
+pub mod field;
+pub mod format;
+pub mod lint;
+pub mod loader;
+pub mod matrix;
 pub mod platform;
+pub mod query_lint;
+pub mod rewrite;
 
+pub use field::*;
+pub use format::*;
+pub use lint::*;
+pub use loader::*;
+pub use matrix::*;
 pub use platform::*;
+pub use query_lint::*;
+pub use rewrite::*;
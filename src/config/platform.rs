@@ -1,13 +1,21 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
+use crate::config::{ConfigFormat, ConfigLinter, FieldSpec, IssueSeverity, QueryLintRule, RewriteRule, SCHEMA_KEY};
 use crate::error::{ConversionError, ConversionResult};
 
+/// Name of the built-in platform that reads and writes the canonical ConvertiX query syntax
+pub const CANONICAL_PLATFORM: &str = "convertix";
+
 /// Platform operators configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Operators {
     pub equal: String,
+    /// Operator matching the whole value, such as `==`; the platform has no exact match when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exact: Option<String>,
     pub and: String,
     pub or: String,
     pub not_equal: String,
@@ -19,40 +27,250 @@ pub struct Operators {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlatformConfig {
     pub operators: Operators,
-    pub fields: HashMap<String, String>,
+    /// Native field name and metadata for each canonical field name
+    pub fields: HashMap<String, FieldSpec>,
+    /// Search page URL template using `{query}` or `{query_base64}` placeholders
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_url: Option<String>,
+    /// Value rewrite rules applied when converting to each target platform
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rewrites: BTreeMap<String, Vec<RewriteRule>>,
+    /// Query lint rules switched on or off; rules not listed are on
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub query_lint: BTreeMap<QueryLintRule, bool>,
+}
+
+impl PlatformConfig {
+    /// Look up the canonical field name for a platform-native field name or alias
+    pub fn canonical_field(&self, native_name: &str) -> Option<&str> {
+        self.resolve_field(native_name).map(|(canonical, _)| canonical)
+    }
+
+    /// Look up a field by its platform-native name or one of its aliases
+    ///
+    /// Native names take precedence over aliases. When several fields share a
+    /// name, the field whose canonical name equals it wins, then the first
+    /// canonical name in sort order.
+    pub fn resolve_field(&self, name: &str) -> Option<(&str, &FieldSpec)> {
+        let best = |matches: &dyn Fn(&FieldSpec) -> bool| {
+            self.fields.iter()
+                .filter(|(_, spec)| matches(spec))
+                .min_by_key(|(canonical, _)| (canonical.as_str() != name, canonical.as_str()))
+                .map(|(canonical, spec)| (canonical.as_str(), spec))
+        };
+
+        best(&|spec| spec.name == name).or_else(|| best(&|spec| spec.aliases.iter().any(|alias| alias == name)))
+    }
+
+    /// Native names and aliases of the fields, sorted and without duplicates
+    pub fn field_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.fields.values()
+            .flat_map(|spec| std::iter::once(&spec.name).chain(&spec.aliases))
+            .cloned()
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Get the rewrite rules for converting to a target platform
+    pub fn rewrite_rules(&self, to_platform: &str) -> &[RewriteRule] {
+        self.rewrites.get(to_platform).map_or(&[], Vec::as_slice)
+    }
+
+    /// Check whether a query lint rule is enabled for queries of the platform
+    pub fn lint_enabled(&self, rule: QueryLintRule) -> bool {
+        self.query_lint.get(&rule).copied().unwrap_or(true)
+    }
+
+    /// Build the canonical platform from the configured platforms
+    ///
+    /// Its fields are the canonical fields of all platforms under their
+    /// canonical names, with the type, description, examples, conversion
+    /// policy and `broad` flag of the first platform in sort order that defines them.
+    /// Its rewrite rules for a target are those of all other platforms for that
+    /// target, in sort order, so a query converts from the canonical platform
+    /// as it does from the platform its fields come from.
+    fn canonical(configs: &HashMap<String, PlatformConfig>) -> Self {
+        let mut platforms: Vec<_> = configs.iter().collect();
+        platforms.sort_by_key(|(platform, _)| *platform);
+
+        let mut rewrites: BTreeMap<String, Vec<RewriteRule>> = BTreeMap::new();
+        for (_, config) in &platforms {
+            for (target, rules) in &config.rewrites {
+                let inherited = rewrites.entry(target.clone()).or_default();
+                for rule in rules {
+                    // The tests are queries of the source platform
+                    let rule = RewriteRule { tests: Vec::new(), ..rule.clone() };
+                    if !inherited.contains(&rule) {
+                        inherited.push(rule);
+                    }
+                }
+            }
+        }
+
+        let mut fields = HashMap::new();
+        for (_, config) in platforms {
+            for (canonical, spec) in &config.fields {
+                fields.entry(canonical.clone()).or_insert_with(|| FieldSpec {
+                    field_type: spec.field_type,
+                    description: spec.description.clone(),
+                    examples: spec.examples.clone(),
+                    on_missing: spec.on_missing,
+                    approximations: spec.approximations.clone(),
+                    broad: spec.broad,
+                    ..FieldSpec::new(canonical.clone())
+                });
+            }
+        }
+
+        Self {
+            operators: Operators {
+                equal: "=".to_string(),
+                exact: Some("==".to_string()),
+                and: "AND".to_string(),
+                or: "OR".to_string(),
+                not_equal: "NOT".to_string(),
+                left_paren: "(".to_string(),
+                right_paren: ")".to_string(),
+            },
+            fields,
+            search_url: None,
+            rewrites,
+            query_lint: BTreeMap::new(),
+        }
+    }
+
+    /// Get the platform-native name of a canonical field
+    pub fn native_name(&self, canonical_field: &str) -> Option<&str> {
+        self.fields.get(canonical_field).map(|spec| spec.name.as_str())
+    }
 }
 
 /// Configuration manager for all platforms
 #[derive(Debug, Clone)]
 pub struct ConfigManager {
     configs: HashMap<String, PlatformConfig>,
+    /// Built-in [`CANONICAL_PLATFORM`], derived from the configured platforms
+    canonical: PlatformConfig,
+    sources: Vec<String>,
 }
 
 impl ConfigManager {
-    /// Load configuration from JSON file
+    /// Load configuration from a JSON, TOML or YAML file, chosen by extension
     pub fn from_file<P: AsRef<Path>>(config_path: P) -> ConversionResult<Self> {
+        let config_path = config_path.as_ref();
         let config_content = fs::read_to_string(config_path)
             .map_err(|e| ConversionError::ConfigurationError(format!("Failed to read config file: {}", e)))?;
-        
-        let configs: HashMap<String, PlatformConfig> = serde_json::from_str(&config_content)
+
+        let source = config_path.display().to_string();
+        let config = ConfigFormat::from_path(config_path).parse(&config_content, &source)?;
+        Self::from_value(config, vec![source])
+    }
+
+    /// Load configuration from JSON text, recording where it came from
+    pub fn from_json_str(config_content: &str, source: &str) -> ConversionResult<Self> {
+        let config: Value = serde_json::from_str(config_content)
             .map_err(|e| ConversionError::ConfigurationError(format!("Failed to parse config file: {}", e)))?;
-        
-        Ok(Self { configs })
+
+        Self::from_value(config, vec![source.to_string()])
     }
 
-    /// Get configuration for a specific platform
+    /// Load configuration from an already merged JSON value and the sources it was merged from
+    ///
+    /// The configuration is rejected if [`ConfigLinter`] reports any error.
+    pub fn from_value(config: Value, sources: Vec<String>) -> ConversionResult<Self> {
+        let config_manager = Self::from_value_unchecked(config, sources)?;
+
+        let errors: Vec<String> = ConfigLinter::lint(&config_manager)
+            .into_iter()
+            .filter(|issue| issue.severity == IssueSeverity::Error)
+            .map(|issue| format!("{}: {}", issue.location, issue.message))
+            .collect();
+        if let Some(first_error) = errors.first() {
+            let more = match errors.len() {
+                1 => String::new(),
+                count => format!(" (and {} more)", count - 1),
+            };
+            return Err(ConversionError::ConfigurationError(format!(
+                "Invalid configuration: {}{}; run 'convertix config check' for details",
+                first_error, more
+            )));
+        }
+
+        Ok(config_manager)
+    }
+
+    /// Load configuration from a JSON value, checking only its shape
+    pub fn from_value_unchecked(config: Value, sources: Vec<String>) -> ConversionResult<Self> {
+        let platforms = match config {
+            Value::Object(platforms) => platforms,
+            _ => {
+                return Err(ConversionError::ConfigurationError(
+                    "Invalid configuration: expected an object of platforms".to_string(),
+                ))
+            }
+        };
+
+        let mut configs = HashMap::new();
+        for (platform, platform_config) in platforms.into_iter().filter(|(key, _)| key != SCHEMA_KEY) {
+            if platform == CANONICAL_PLATFORM {
+                return Err(ConversionError::ConfigurationError(format!(
+                    "Invalid configuration: platform name '{}' is reserved for the canonical query syntax",
+                    CANONICAL_PLATFORM
+                )));
+            }
+            let platform_config: PlatformConfig = serde_json::from_value(platform_config).map_err(|e| {
+                ConversionError::ConfigurationError(format!("Invalid configuration of platform '{}': {}", platform, e))
+            })?;
+            configs.insert(platform, platform_config);
+        }
+
+        let canonical = PlatformConfig::canonical(&configs);
+        Ok(Self { configs, canonical, sources })
+    }
+
+    /// Get the sources this configuration was loaded from, lowest precedence first
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    /// Get configuration for a specific platform, including the canonical platform
     pub fn get_platform_config(&self, platform: &str) -> ConversionResult<&PlatformConfig> {
+        if platform == CANONICAL_PLATFORM {
+            return Ok(&self.canonical);
+        }
         self.configs.get(platform)
             .ok_or_else(|| ConversionError::UnsupportedPlatform(platform.to_string()))
     }
 
-    /// Get list of supported platforms
+    /// Get list of configured platforms, sorted by name; the canonical platform is not included
     pub fn get_supported_platforms(&self) -> Vec<String> {
-        self.configs.keys().cloned().collect()
+        let mut platforms: Vec<String> = self.configs.keys().cloned().collect();
+        platforms.sort();
+        platforms
     }
 
-    /// Check if a platform is supported
+    /// Check if a platform is supported, including the canonical platform
     pub fn is_platform_supported(&self, platform: &str) -> bool {
-        self.configs.contains_key(platform)
+        platform == CANONICAL_PLATFORM || self.configs.contains_key(platform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+
+    #[test]
+    fn embedded_aliases_resolve_to_their_field() {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        for platform in manager.get_supported_platforms() {
+            let config = manager.get_platform_config(&platform).unwrap();
+            for name in ["header", "headers", "http.header"] {
+                assert_eq!(config.canonical_field(name), Some("header"), "{} on {}", name, platform);
+            }
+        }
+        assert_eq!(manager.get_platform_config("fofa").unwrap().canonical_field("foo.title"), None);
     }
 }
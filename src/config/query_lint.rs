@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Check for queries that are valid but probably not what their author meant
+///
+/// Every rule is enabled unless a platform turns it off in its `query_lint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QueryLintRule {
+    /// Operands of an `AND` that cannot match together, such as `port="80" && port="443"`
+    Contradiction,
+    /// A clause and its negation in an `OR`, which matches every record
+    Tautology,
+    /// Negations of different values of a single-valued field in an `OR`, which match every record
+    OrOfNegations,
+    /// A query matching only on fields marked `broad`, such as a lone `body=` search
+    BroadSearch,
+    /// An unquoted string value the engine may split into several words
+    UnquotedValue,
+    /// A value with letters on a field marked `case_sensitive`
+    CaseSensitive,
+}
+
+impl QueryLintRule {
+    /// All rules, in the order they are reported
+    pub const ALL: [QueryLintRule; 6] = [
+        QueryLintRule::Contradiction,
+        QueryLintRule::Tautology,
+        QueryLintRule::OrOfNegations,
+        QueryLintRule::BroadSearch,
+        QueryLintRule::UnquotedValue,
+        QueryLintRule::CaseSensitive,
+    ];
+}
+
+impl fmt::Display for QueryLintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryLintRule::Contradiction => write!(f, "contradiction"),
+            QueryLintRule::Tautology => write!(f, "tautology"),
+            QueryLintRule::OrOfNegations => write!(f, "or-of-negations"),
+            QueryLintRule::BroadSearch => write!(f, "broad-search"),
+            QueryLintRule::UnquotedValue => write!(f, "unquoted-value"),
+            QueryLintRule::CaseSensitive => write!(f, "case-sensitive"),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Rule replacing a clause of the source platform by a clause of the target platform
+///
+/// Rules handle mappings that change values rather than just field names,
+/// such as Hunter `is_web=true` and FOFA `type="subdomain"`. They are
+/// configured on the source platform under `rewrites`, by target platform,
+/// and the first matching rule wins over the plain field mapping.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewriteRule {
+    pub from: RewriteMatch,
+    pub to: RewriteTarget,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Example conversions checked by `convertix config check`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tests: Vec<RewriteTest>,
+}
+
+/// Clauses a rewrite rule applies to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewriteMatch {
+    /// Canonical field of the source platform
+    pub field: String,
+    /// Regular expression the whole value must match; any value when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<ValuePattern>,
+}
+
+/// Value pattern of a rewrite rule, compiled once when the configuration is read
+///
+/// It is written in the configuration as a plain string. An invalid pattern
+/// keeps its error, which the [`ConfigLinter`](crate::config::ConfigLinter) reports.
+#[derive(Debug, Clone)]
+pub struct ValuePattern {
+    pattern: String,
+    regex: Result<Regex, regex::Error>,
+}
+
+/// Clause a rewrite rule produces
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewriteTarget {
+    /// Canonical field of the target platform
+    pub field: String,
+    /// Replacement value, which may refer to groups of the value pattern as `$1` or
+    /// `${name}`; the value of the query when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+/// Query of the source platform and its expected conversion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewriteTest {
+    pub query: String,
+    pub expected: String,
+}
+
+impl ValuePattern {
+    /// Compile a pattern, anchored to match the whole value
+    pub fn new(pattern: impl Into<String>) -> Self {
+        let pattern = pattern.into();
+        let regex = Regex::new(&format!("^(?:{})$", pattern));
+        Self { pattern, regex }
+    }
+
+    /// Pattern as written in the configuration
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Compiled pattern, or the reason it does not compile
+    pub fn regex(&self) -> Result<&Regex, &regex::Error> {
+        self.regex.as_ref()
+    }
+}
+
+impl PartialEq for ValuePattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl Serialize for ValuePattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.pattern)
+    }
+}
+
+impl<'de> Deserialize<'de> for ValuePattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl RewriteRule {
+    /// Compiled value pattern, `None` when the rule matches any value
+    pub fn value_regex(&self) -> Result<Option<&Regex>, &regex::Error> {
+        self.from.value.as_ref().map(ValuePattern::regex).transpose()
+    }
+
+    /// Get the rewritten value if the rule matches a value of its field
+    pub fn rewrite_value(&self, value: &str) -> Option<String> {
+        let Ok(regex) = self.value_regex() else {
+            return None;
+        };
+
+        match (regex, &self.to.value) {
+            (None, None) => Some(value.to_string()),
+            (None, Some(replacement)) => Some(replacement.clone()),
+            (Some(regex), replacement) => {
+                let captures = regex.captures(value)?;
+                let mut rewritten = String::new();
+                match replacement {
+                    Some(replacement) => captures.expand(replacement, &mut rewritten),
+                    None => rewritten.push_str(value),
+                }
+                Some(rewritten)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: Option<&str>, replacement: Option<&str>) -> RewriteRule {
+        RewriteRule {
+            from: RewriteMatch { field: "protocol".to_string(), value: pattern.map(ValuePattern::new) },
+            to: RewriteTarget { field: "protocol".to_string(), value: replacement.map(str::to_string) },
+            description: None,
+            tests: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn patterns_match_the_whole_value() {
+        let rule = rule(Some("https"), Some("ssl/http"));
+        assert_eq!(rule.rewrite_value("https").as_deref(), Some("ssl/http"));
+        assert_eq!(rule.rewrite_value("xhttps"), None);
+        assert_eq!(rule.rewrite_value("https2"), None);
+
+        // Alternatives are anchored as a whole, not only the first and last one
+        let rule = self::rule(Some("http|https"), Some("web"));
+        assert_eq!(rule.rewrite_value("https").as_deref(), Some("web"));
+        assert_eq!(rule.rewrite_value("httpx"), None);
+    }
+
+    #[test]
+    fn replacements_expand_groups() {
+        let rule = rule(Some("ssl/(.+)"), Some("$1"));
+        assert_eq!(rule.rewrite_value("ssl/imap").as_deref(), Some("imap"));
+        assert_eq!(rule.rewrite_value("imap"), None);
+
+        let rule = self::rule(Some("(?<service>[a-z]+)-(?<version>\\d+)"), Some("${service}/v${version}"));
+        assert_eq!(rule.rewrite_value("http-2").as_deref(), Some("http/v2"));
+    }
+
+    #[test]
+    fn an_omitted_replacement_keeps_the_value() {
+        assert_eq!(rule(Some("ssl/.+"), None).rewrite_value("ssl/imap").as_deref(), Some("ssl/imap"));
+        assert_eq!(rule(Some("ssl/.+"), None).rewrite_value("imap"), None);
+        assert_eq!(rule(None, None).rewrite_value("imap").as_deref(), Some("imap"));
+        assert_eq!(rule(None, Some("web")).rewrite_value("imap").as_deref(), Some("web"));
+    }
+
+    #[test]
+    fn patterns_are_compiled_when_read() {
+        let rule: RewriteMatch = serde_json::from_str(r#"{"field": "protocol", "value": "ssl/(.+"}"#).unwrap();
+        let pattern = rule.value.unwrap();
+        assert_eq!(pattern.as_str(), "ssl/(.+");
+        assert!(pattern.regex().is_err());
+        assert_eq!(serde_json::to_string(&pattern).unwrap(), r#""ssl/(.+""#);
+    }
+
+    #[test]
+    fn invalid_patterns_match_nothing() {
+        assert!(rule(Some("ssl/(.+"), Some("$1")).value_regex().is_err());
+        assert_eq!(rule(Some("ssl/(.+"), Some("$1")).rewrite_value("ssl/imap"), None);
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt;
+
+/// Parsed query, independent of the syntax of any platform
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr {
+    /// Field comparison such as `title="nginx"`
+    Clause(Clause),
+    /// Free-text search term such as `"nginx"`
+    Term(QueryValue),
+    /// Negated sub-expression such as `NOT (a OR b)`
+    Not(Box<Expr>),
+    /// All sub-expressions must match
+    And(Vec<Expr>),
+    /// At least one sub-expression must match
+    Or(Vec<Expr>),
+    /// Parenthesized sub-expression, kept to preserve the grouping of the input
+    Group(Box<Expr>),
+}
+
+/// Comparison of a field with a value
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Clause {
+    /// Field name as written for the platform
+    pub field: String,
+    /// `field!=value` or `NOT field:value`
+    pub negated: bool,
+    /// `field==value`, matching the whole value
+    pub exact: bool,
+    pub value: QueryValue,
+}
+
+/// Value of a clause or search term
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryValue {
+    /// Unescaped text
+    pub text: String,
+    /// Whether the value was written in double quotes
+    pub quoted: bool,
+}
+
+impl QueryValue {
+    /// Create a double-quoted value
+    pub fn quoted(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            quoted: true,
+        }
+    }
+}
+
+impl fmt::Display for QueryValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.quoted {
+            write!(f, "\"{}\"", self.text.replace('\\', "\\\\").replace('"', "\\\""))
+        } else {
+            write!(f, "{}", self.text)
+        }
+    }
+}
+
+impl Expr {
+    /// Collect the clauses of the expression, each with whether it is effectively negated
+    ///
+    /// A clause under an odd number of `NOT`s is negated even if written as
+    /// `field=value`.
+    pub fn clauses(&self) -> Vec<(&Clause, bool)> {
+        let mut clauses = Vec::new();
+        self.collect_clauses(false, &mut clauses);
+        clauses
+    }
+
+    fn collect_clauses<'a>(&'a self, negated: bool, clauses: &mut Vec<(&'a Clause, bool)>) {
+        match self {
+            Expr::Clause(clause) => clauses.push((clause, clause.negated != negated)),
+            Expr::Term(_) => {}
+            Expr::Not(inner) => inner.collect_clauses(!negated, clauses),
+            Expr::And(children) | Expr::Or(children) => {
+                for child in children {
+                    child.collect_clauses(negated, clauses);
+                }
+            }
+            Expr::Group(inner) => inner.collect_clauses(negated, clauses),
+        }
+    }
+
+    /// Rebuild the expression bottom-up, replacing or removing clauses
+    ///
+    /// `map` returns the replacement of a clause, or `None` to remove it. An
+    /// `AND`/`OR` left with a single operand is replaced by that operand, and
+    /// `None` is returned when nothing is left.
+    pub fn map_clauses<E>(
+        self,
+        map: &mut impl FnMut(Clause) -> Result<Option<Expr>, E>,
+    ) -> Result<Option<Expr>, E> {
+        Ok(match self {
+            Expr::Clause(clause) => map(clause)?,
+            Expr::Term(value) => Some(Expr::Term(value)),
+            Expr::Not(inner) => inner.map_clauses(map)?.map(|inner| Expr::Not(Box::new(inner))),
+            Expr::And(children) => Self::rebuild(children, map, Expr::And)?,
+            Expr::Or(children) => Self::rebuild(children, map, Expr::Or)?,
+            Expr::Group(inner) => inner.map_clauses(map)?.map(|inner| Expr::Group(Box::new(inner))),
+        })
+    }
+
+    fn rebuild<E>(
+        children: Vec<Expr>,
+        map: &mut impl FnMut(Clause) -> Result<Option<Expr>, E>,
+        combine: fn(Vec<Expr>) -> Expr,
+    ) -> Result<Option<Expr>, E> {
+        let mut kept = Vec::new();
+        for child in children {
+            if let Some(child) = child.map_clauses(map)? {
+                kept.push(child);
+            }
+        }
+
+        Ok(match kept.len() {
+            0 => None,
+            1 => kept.pop(),
+            _ => Some(combine(kept)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clause(field: &str) -> Expr {
+        Expr::Clause(Clause {
+            field: field.to_string(),
+            negated: false,
+            exact: false,
+            value: QueryValue::quoted("x"),
+        })
+    }
+
+    /// Remove the clauses on `field`, keeping the others
+    fn drop_field(expr: Expr, field: &str) -> Option<Expr> {
+        expr.map_clauses(&mut |clause: Clause| {
+            Ok::<_, ()>((clause.field != field).then_some(Expr::Clause(clause)))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn operands_left_alone_replace_their_operator() {
+        let expr = Expr::And(vec![clause("a"), Expr::Group(Box::new(Expr::Or(vec![clause("b"), clause("c")])))]);
+        assert_eq!(
+            drop_field(expr.clone(), "b"),
+            Some(Expr::And(vec![clause("a"), Expr::Group(Box::new(clause("c")))]))
+        );
+        assert_eq!(
+            drop_field(expr, "a"),
+            Some(Expr::Group(Box::new(Expr::Or(vec![clause("b"), clause("c")]))))
+        );
+
+        let expr = Expr::Or(vec![clause("a"), clause("b"), clause("c")]);
+        assert_eq!(drop_field(expr, "b"), Some(Expr::Or(vec![clause("a"), clause("c")])));
+    }
+
+    #[test]
+    fn emptied_operators_and_negations_are_dropped() {
+        let expr = Expr::And(vec![clause("a"), Expr::Not(Box::new(Expr::Group(Box::new(Expr::Or(vec![clause("b"), clause("b")])))))]);
+        assert_eq!(drop_field(expr, "b"), Some(clause("a")));
+        assert_eq!(drop_field(Expr::Not(Box::new(clause("a"))), "a"), None);
+        assert_eq!(drop_field(Expr::And(vec![clause("a"), clause("a")]), "a"), None);
+    }
+
+    #[test]
+    fn terms_are_kept_and_errors_stop_the_rebuild() {
+        let term = Expr::Term(QueryValue::quoted("nginx"));
+        assert_eq!(drop_field(Expr::And(vec![term.clone(), clause("a")]), "a"), Some(term));
+
+        let mut visited = Vec::new();
+        let result = Expr::Or(vec![clause("a"), clause("b"), clause("c")]).map_clauses(&mut |clause: Clause| {
+            visited.push(clause.field.clone());
+            if clause.field == "b" { Err(clause.field) } else { Ok(Some(Expr::Clause(clause))) }
+        });
+        assert_eq!(result, Err("b".to_string()));
+        assert_eq!(visited, ["a", "b"]);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::config::PlatformConfig;
+use crate::converter::{Expr, QueryValue};
+use crate::error::{ConversionError, ConversionResult};
+use std::fmt;
+
+/// Largest number of distinct conditions two queries are compared on, as every
+/// combination of them is evaluated
+pub const MAX_EQUIVALENCE_CONDITIONS: usize = 20;
+
+/// Condition a record either matches or not: a clause in canonical field names, or a search term
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    /// Canonical field name, `None` for a search term
+    pub field: Option<String>,
+    pub value: String,
+    /// Whether the clause matches the whole value (`==`)
+    pub exact: bool,
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field {
+            Some(field) => {
+                let operator = if self.exact { "==" } else { "=" };
+                write!(f, "{}{}{}", field, operator, QueryValue::quoted(self.value.as_str()))
+            }
+            None => write!(f, "{}", QueryValue::quoted(self.value.as_str())),
+        }
+    }
+}
+
+/// Result of comparing two queries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence {
+    /// Both queries match the same records
+    Equal,
+    /// A record matching exactly the `matching` conditions, and none of the
+    /// others, is matched by the first query only (`matches_a`) or the second only
+    Different { matching: Vec<Condition>, matches_a: bool },
+}
+
+/// Query reduced to boolean operations on the indices of its conditions
+enum Formula {
+    Condition(usize),
+    Not(Box<Formula>),
+    And(Vec<Formula>),
+    Or(Vec<Formula>),
+}
+
+impl Formula {
+    fn eval(&self, assignment: u32) -> bool {
+        match self {
+            Formula::Condition(index) => assignment & (1 << index) != 0,
+            Formula::Not(inner) => !inner.eval(assignment),
+            Formula::And(children) => children.iter().all(|child| child.eval(assignment)),
+            Formula::Or(children) => children.iter().any(|child| child.eval(assignment)),
+        }
+    }
+}
+
+/// Checker deciding whether two queries, possibly of different platforms, match the same records
+///
+/// Clauses are compared by canonical field name and value, so `title="a"` on
+/// FOFA and `title:"a"` on Quake are the same condition. Distinct conditions are
+/// treated as independent: `port="80"` and `port="443"` may both hold.
+/// Rewrite rules are not applied, the queries are compared as written.
+pub struct EquivalenceChecker;
+
+impl EquivalenceChecker {
+    /// Compare two parsed queries
+    ///
+    /// Combinations of conditions are tried from the fewest matching ones up, so
+    /// a reported difference names as few conditions as possible.
+    pub fn check(a: &Expr, a_config: &PlatformConfig, b: &Expr, b_config: &PlatformConfig) -> ConversionResult<Equivalence> {
+        let mut conditions = Vec::new();
+        let formula_a = Self::formula(a, a_config, &mut conditions);
+        let formula_b = Self::formula(b, b_config, &mut conditions);
+
+        if conditions.len() > MAX_EQUIVALENCE_CONDITIONS {
+            return Err(ConversionError::TooManyConditions {
+                count: conditions.len(),
+                limit: MAX_EQUIVALENCE_CONDITIONS,
+            });
+        }
+
+        let mut assignments: Vec<u32> = (0..1u32 << conditions.len()).collect();
+        assignments.sort_by_key(|assignment| assignment.count_ones());
+
+        for assignment in assignments {
+            let matches_a = formula_a.eval(assignment);
+            if matches_a != formula_b.eval(assignment) {
+                let matching = conditions
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| assignment & (1 << index) != 0)
+                    .map(|(_, condition)| condition.clone())
+                    .collect();
+                return Ok(Equivalence::Different { matching, matches_a });
+            }
+        }
+
+        Ok(Equivalence::Equal)
+    }
+
+    /// Reduce an expression to a formula, adding its new conditions to `conditions`
+    fn formula(expr: &Expr, config: &PlatformConfig, conditions: &mut Vec<Condition>) -> Formula {
+        let mut index_of = |condition: Condition| match conditions.iter().position(|known| *known == condition) {
+            Some(index) => index,
+            None => {
+                conditions.push(condition);
+                conditions.len() - 1
+            }
+        };
+
+        match expr {
+            Expr::Clause(clause) => {
+                let field = config.canonical_field(&clause.field).unwrap_or(&clause.field);
+                let index = index_of(Condition {
+                    field: Some(field.to_string()),
+                    value: clause.value.text.clone(),
+                    exact: clause.exact,
+                });
+                if clause.negated {
+                    Formula::Not(Box::new(Formula::Condition(index)))
+                } else {
+                    Formula::Condition(index)
+                }
+            }
+            Expr::Term(value) => Formula::Condition(index_of(Condition {
+                field: None,
+                value: value.text.clone(),
+                exact: false,
+            })),
+            Expr::Not(inner) => Formula::Not(Box::new(Self::formula(inner, config, conditions))),
+            Expr::And(children) => {
+                Formula::And(children.iter().map(|child| Self::formula(child, config, conditions)).collect())
+            }
+            Expr::Or(children) => {
+                Formula::Or(children.iter().map(|child| Self::formula(child, config, conditions)).collect())
+            }
+            Expr::Group(inner) => Self::formula(inner, config, conditions),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigManager, EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+    use crate::converter::QueryParser;
+
+    fn check(a: &str, a_platform: &str, b: &str, b_platform: &str) -> ConversionResult<Equivalence> {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        let (a_config, b_config) = (manager.get_platform_config(a_platform)?, manager.get_platform_config(b_platform)?);
+        EquivalenceChecker::check(&QueryParser::parse(a, a_config)?, a_config, &QueryParser::parse(b, b_config)?, b_config)
+    }
+
+    fn condition(field: &str, value: &str) -> Condition {
+        Condition { field: Some(field.to_string()), value: value.to_string(), exact: false }
+    }
+
+    #[test]
+    fn rewritten_queries_are_equal() {
+        assert_eq!(
+            check(r#"title="a" && (port="80" || port="443")"#, "fofa", r#"(web.title="a" && ip.port="80") || (ip.port="443" && web.title="a")"#, "hunter").unwrap(),
+            Equivalence::Equal
+        );
+        assert_eq!(check(r#"title!="a" && port!="80""#, "fofa", r#"NOT (title:"a" OR port:"80")"#, "quake").unwrap(), Equivalence::Equal);
+    }
+
+    #[test]
+    fn differences_name_the_fewest_conditions() {
+        assert_eq!(
+            check(r#"port="80" || port="443""#, "fofa", "port:80", "quake").unwrap(),
+            Equivalence::Different { matching: vec![condition("port", "443")], matches_a: true }
+        );
+        // No condition at all tells these apart
+        assert_eq!(
+            check(r#"title!="a""#, "fofa", r#"title="a""#, "fofa").unwrap(),
+            Equivalence::Different { matching: Vec::new(), matches_a: true }
+        );
+        assert_eq!(
+            check(r#"title="a" && port="80" && country="CN""#, "fofa", r#"title="a" && port="80""#, "fofa").unwrap(),
+            Equivalence::Different { matching: vec![condition("title", "a"), condition("port", "80")], matches_a: false }
+        );
+    }
+
+    #[test]
+    fn values_are_compared_as_written() {
+        assert!(matches!(check(r#"title="a""#, "fofa", r#"title="A""#, "fofa").unwrap(), Equivalence::Different { .. }));
+        assert_eq!(check(r#"title="a""#, "fofa", "title=a", "fofa").unwrap(), Equivalence::Equal);
+    }
+
+    #[test]
+    fn exact_matches_are_their_own_conditions() {
+        assert_eq!(check(r#"title=="a""#, "fofa", r#"web.title=="a""#, "hunter").unwrap(), Equivalence::Equal);
+        assert!(matches!(
+            check(r#"title=="a""#, "fofa", r#"title="a""#, "fofa").unwrap(),
+            Equivalence::Different { matching, .. } if matching[0].to_string() == r#"title=="a""#
+        ));
+    }
+
+    #[test]
+    fn too_many_conditions_are_rejected() {
+        let query = |count: usize| (0..count).map(|port| format!("port=\"{}\"", port)).collect::<Vec<_>>().join(" || ");
+        assert_eq!(check(&query(MAX_EQUIVALENCE_CONDITIONS), "fofa", &query(MAX_EQUIVALENCE_CONDITIONS), "fofa").unwrap(), Equivalence::Equal);
+        assert!(matches!(
+            check(&query(MAX_EQUIVALENCE_CONDITIONS + 1), "fofa", r#"port="0""#, "fofa"),
+            Err(ConversionError::TooManyConditions { count, limit: MAX_EQUIVALENCE_CONDITIONS }) if count == MAX_EQUIVALENCE_CONDITIONS + 1
+        ));
+    }
+}
@@ -1,50 +1,289 @@
-use crate::config::PlatformConfig;
-use regex::Regex;
+use crate::config::{FieldType, MatchMode, MissingFieldPolicy, PlatformConfig};
+use crate::converter::{Clause, Expr, FieldMapping, QueryParser, QueryValue};
+use crate::error::{ConversionError, ConversionResult};
+
+/// Query with its fields converted to a target platform
+pub struct FieldConversion {
+    pub expr: Expr,
+    pub mapped_fields: Vec<FieldMapping>,
+    /// Source fields without an equivalent on the target platform
+    pub unmapped_fields: Vec<String>,
+    pub warnings: Vec<String>,
+}
 
 /// Field converter for transforming field names between platforms
 pub struct FieldConverter;
 
 impl FieldConverter {
-    /// Convert field prefixes between platforms
+    /// Convert the fields of a parsed query between platforms
+    ///
+    /// Clauses matching a rewrite rule of the source platform for the target
+    /// are rewritten as the rule says. Otherwise native names and aliases of
+    /// the source platform are replaced by the native name on the target
+    /// platform. A field without a target equivalent is replaced by its
+    /// approximation for the target platform if it has one, and otherwise
+    /// handled by its `on_missing` policy, falling back to `default_policy`.
+    /// An exact match (`==`) the target cannot express is handled by the same policy.
     pub fn convert_fields(
-        query: &str,
+        expr: Expr,
         from_config: &PlatformConfig,
         to_config: &PlatformConfig,
-    ) -> String {
-        let mut result = query.to_string();
-
-        // Sort field pairs by field name length, process longer names first to avoid partial matching
-        let mut field_pairs: Vec<_> = from_config.fields.iter().collect();
-        field_pairs.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
-
-        for (field_name, from_field_prefix) in field_pairs {
-            if let Some(to_field_prefix) = to_config.fields.get(field_name) {
-                if from_field_prefix != to_field_prefix {
-                    // Create patterns for both = and : operators
-                    let from_patterns = [
-                        format!("{}=", from_field_prefix),
-                        format!("{}:", from_field_prefix),
-                    ];
-
-                    for from_pattern in from_patterns {
-                        // Use regex for precise matching with word boundaries
-                        let from_pattern_escaped = regex::escape(&from_pattern);
-                        let pattern = format!(r"\b{}", from_pattern_escaped);
-
-                        if let Ok(re) = Regex::new(&pattern) {
-                            // Determine the target operator based on the original operator
-                            let replacement = if from_pattern.ends_with('=') {
-                                format!("{}=", to_field_prefix)
-                            } else {
-                                format!("{}:", to_field_prefix)
-                            };
-                            result = re.replace_all(&result, replacement.as_str()).to_string();
-                        }
+        to_platform: &str,
+        default_policy: MissingFieldPolicy,
+    ) -> ConversionResult<FieldConversion> {
+        let mut mapped_fields: Vec<FieldMapping> = Vec::new();
+        let mut unmapped_fields: Vec<String> = Vec::new();
+        let mut warnings: Vec<String> = Vec::new();
+        let mut inexact_fields: Vec<String> = Vec::new();
+        let target = to_platform.to_uppercase();
+
+        let converted = expr.map_clauses(&mut |mut clause: Clause| {
+            let Some((canonical, spec)) = from_config.resolve_field(&clause.field) else {
+                return Ok(Some(Expr::Clause(clause)));
+            };
+            let source_field = clause.field.clone();
+            let policy = spec.on_missing.unwrap_or(default_policy);
+
+            // The first matching rewrite rule takes precedence over the field mapping
+            let rewrite = from_config.rewrite_rules(to_platform).iter().find_map(|rule| {
+                let value = (rule.from.field == canonical).then(|| rule.rewrite_value(&clause.value.text))??;
+                to_config.native_name(&rule.to.field).map(|native| (native, Some((rule.to.field.as_str(), value))))
+            });
+            let mapped = rewrite.or_else(|| to_config.native_name(canonical).map(|native| (native, None)));
+
+            // Canonical field of the target platform the clause matches
+            let target_field = match mapped {
+                Some((native, rewritten)) => {
+                    if !mapped_fields.iter().any(|mapping| mapping.source == clause.field) {
+                        mapped_fields.push(FieldMapping {
+                            field: canonical.to_string(),
+                            source: clause.field.clone(),
+                            target: native.to_string(),
+                        });
+                    }
+                    let target_field = rewritten.as_ref().map_or(canonical, |(field, _)| *field);
+                    clause.field = native.to_string();
+                    if let Some((field, value)) = rewritten.filter(|(_, value)| *value != clause.value.text) {
+                        clause.value = Self::typed_value(to_config, field, value);
+                    }
+                    target_field
+                }
+                None => {
+                    let first_occurrence = !unmapped_fields.contains(&clause.field);
+                    if first_occurrence {
+                        unmapped_fields.push(clause.field.clone());
+                    }
+
+                    let approximation = spec.approximations.get(to_platform).and_then(|approximation| {
+                        to_config.native_name(&approximation.field).map(|native| (native, approximation))
+                    });
+                    let Some((native, approximation)) = approximation else {
+                        return match policy {
+                            MissingFieldPolicy::Keep => {
+                                if first_occurrence {
+                                    warnings.push(format!(
+                                        "Field '{}' has no equivalent on {} and was left unchanged",
+                                        clause.field, target
+                                    ));
+                                }
+                                Ok(Some(Expr::Clause(clause)))
+                            }
+                            MissingFieldPolicy::Drop => {
+                                if first_occurrence {
+                                    warnings.push(format!(
+                                        "Field '{}' has no equivalent on {} and its clauses were dropped, so the query may match different results",
+                                        clause.field, target
+                                    ));
+                                }
+                                Ok(None)
+                            }
+                            MissingFieldPolicy::Error => Err(ConversionError::NoEquivalentField {
+                                platform: to_platform.to_string(),
+                                field: clause.field,
+                            }),
+                        };
+                    };
+
+                    if first_occurrence {
+                        warnings.push(format!(
+                            "Field '{}' has no equivalent on {} and was approximated with '{}'",
+                            clause.field, target, native
+                        ));
+                    }
+                    clause.field = native.to_string();
+                    if let Some(value) = &approximation.value {
+                        clause.value = Self::typed_value(to_config, &approximation.field, value.clone());
                     }
+                    approximation.field.as_str()
                 }
+            };
+
+            // An exact match (`==`) the target cannot express is handled by the policy of the field
+            let exact_supported = to_config.operators.exact.is_some()
+                && to_config.fields.get(target_field).is_none_or(|spec| spec.supports(MatchMode::Exact))
+                && (!clause.negated || QueryParser::has_prefix_not(&to_config.operators));
+            if !clause.exact || exact_supported {
+                return Ok(Some(Expr::Clause(clause)));
+            }
+
+            let first_inexact = !inexact_fields.contains(&source_field);
+            if first_inexact {
+                inexact_fields.push(source_field.clone());
+            }
+            let missing = if clause.negated && to_config.operators.exact.is_some() { "negated exact match" } else { "exact match" };
+            match policy {
+                MissingFieldPolicy::Keep => {
+                    if first_inexact {
+                        warnings.push(format!(
+                            "Field '{}' has no {} on {} and was matched with '{}' instead, so the query may match different results",
+                            source_field, missing, target, to_config.operators.equal
+                        ));
+                    }
+                    clause.exact = false;
+                    Ok(Some(Expr::Clause(clause)))
+                }
+                MissingFieldPolicy::Drop => {
+                    if first_inexact {
+                        warnings.push(format!(
+                            "Field '{}' has no {} on {} and its exact clauses were dropped, so the query may match different results",
+                            source_field, missing, target
+                        ));
+                    }
+                    Ok(None)
+                }
+                MissingFieldPolicy::Error => Err(ConversionError::UnsupportedMatchMode {
+                    platform: to_platform.to_string(),
+                    field: source_field,
+                    mode: MatchMode::Exact.to_string(),
+                    span: None,
+                }),
             }
-        }
+        })?;
+
+        // Every clause was dropped, nothing is left to search for
+        let Some(expr) = converted else {
+            return Err(ConversionError::NoEquivalentField {
+                platform: to_platform.to_string(),
+                field: unmapped_fields.join(", "),
+            });
+        };
+
+        Ok(FieldConversion {
+            expr,
+            mapped_fields,
+            unmapped_fields,
+            warnings,
+        })
+    }
+
+    /// Value written for a canonical field of the target platform: numbers and
+    /// booleans unquoted, anything else in double quotes
+    fn typed_value(to_config: &PlatformConfig, field: &str, text: String) -> QueryValue {
+        let quoted = !to_config.fields.get(field).is_some_and(|spec| {
+            matches!(spec.field_type, FieldType::Number | FieldType::Boolean) && spec.field_type.accepts(&text)
+        });
+        QueryValue { text, quoted }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigManager, EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+    use crate::converter::{QueryParser, QueryRenderer};
+
+    fn convert(query: &str, from: &str, to: &str, policy: MissingFieldPolicy) -> ConversionResult<(String, FieldConversion)> {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        let (from_config, to_config) = (manager.get_platform_config(from).unwrap(), manager.get_platform_config(to).unwrap());
+        let expr = QueryParser::parse(query, from_config)?;
+        let conversion = FieldConverter::convert_fields(expr, from_config, to_config, to, policy)?;
+        Ok((QueryRenderer::render(&conversion.expr, to_config, to)?, conversion))
+    }
+
+    #[test]
+    fn keep_leaves_the_clause_and_warns() {
+        let (query, conversion) = convert(r#"title:"a" AND is_domain:true"#, "quake", "fofa", MissingFieldPolicy::Keep).unwrap();
+        assert_eq!(query, r#"title="a" && is_domain=true"#);
+        assert_eq!(conversion.unmapped_fields, ["is_domain"]);
+        assert_eq!(conversion.warnings, ["Field 'is_domain' has no equivalent on FOFA and was left unchanged"]);
+    }
+
+    #[test]
+    fn drop_removes_the_clause_and_warns_once() {
+        let (query, conversion) = convert(
+            r#"title:"a" AND (is_domain:true OR NOT is_domain:false)"#,
+            "quake",
+            "fofa",
+            MissingFieldPolicy::Drop,
+        )
+        .unwrap();
+        assert_eq!(query, r#"title="a""#);
+        assert_eq!(conversion.warnings.len(), 1);
+        assert!(conversion.warnings[0].contains("its clauses were dropped"));
+    }
+
+    #[test]
+    fn error_rejects_the_conversion() {
+        let result = convert(r#"title:"a" AND is_domain:true"#, "quake", "fofa", MissingFieldPolicy::Error);
+        let Err(error) = result else { panic!("conversion succeeded") };
+        assert!(matches!(&error, ConversionError::NoEquivalentField { field, .. } if field == "is_domain"));
+        assert_eq!(error.code().code, "CX0111");
+    }
+
+    #[test]
+    fn dropping_every_clause_is_an_error() {
+        let result = convert("is_domain:true OR is_domain:false", "quake", "fofa", MissingFieldPolicy::Drop);
+        let Err(error) = result else { panic!("conversion succeeded") };
+        assert!(matches!(&error, ConversionError::NoEquivalentField { field, .. } if field == "is_domain"));
+        assert_eq!(error.code().code, "CX0111");
+    }
+
+    #[test]
+    fn exact_matches_are_kept_where_the_target_has_them() {
+        let (query, conversion) = convert(r#"title=="a" && port="80""#, "fofa", "hunter", MissingFieldPolicy::Error).unwrap();
+        assert_eq!(query, r#"web.title=="a" && ip.port="80""#);
+        assert!(conversion.warnings.is_empty());
+    }
+
+    #[test]
+    fn exact_matches_fall_back_to_the_policy_elsewhere() {
+        let (query, conversion) = convert(r#"title=="a" && title=="b" && port="80""#, "fofa", "quake", MissingFieldPolicy::Keep).unwrap();
+        assert_eq!(query, r#"title:"a" AND title:"b" AND port:"80""#);
+        assert_eq!(
+            conversion.warnings,
+            ["Field 'title' has no exact match on QUAKE and was matched with ':' instead, so the query may match different results"]
+        );
+
+        let (query, conversion) = convert(r#"title=="a" && port="80""#, "fofa", "quake", MissingFieldPolicy::Drop).unwrap();
+        assert_eq!(query, r#"port:"80""#);
+        assert!(conversion.warnings[0].contains("its exact clauses were dropped"));
+
+        let result = convert(r#"title=="a""#, "fofa", "quake", MissingFieldPolicy::Error);
+        assert!(matches!(result, Err(ConversionError::UnsupportedMatchMode { field, mode, .. }) if field == "title" && mode == "exact"));
+    }
+
+    #[test]
+    fn negated_exact_matches_need_a_prefix_not() {
+        let (query, _) = convert(r#"NOT title=="a""#, "convertix", "convertix", MissingFieldPolicy::Error).unwrap();
+        assert_eq!(query, r#"NOT title=="a""#);
+        let (query, conversion) = convert(r#"NOT title=="a""#, "convertix", "fofa", MissingFieldPolicy::Keep).unwrap();
+        assert_eq!(query, r#"title!="a""#);
+        assert!(conversion.warnings[0].contains("has no negated exact match on FOFA"));
+    }
+
+    #[test]
+    fn rewritten_values_are_quoted_by_the_target_type() {
+        let (query, _) = convert(r#"type="subdomain" && port=80"#, "fofa", "hunter", MissingFieldPolicy::Keep).unwrap();
+        assert_eq!(query, "is_web=true && ip.port=80");
+        let (query, _) = convert("is_web=true", "hunter", "fofa", MissingFieldPolicy::Keep).unwrap();
+        assert_eq!(query, r#"type="subdomain""#);
+    }
 
-        result
+    #[test]
+    fn the_policy_of_the_field_overrides_the_default() {
+        // `is_honeypot` is configured with `"on_missing": "drop"`
+        let (query, conversion) = convert(r#"title="a" && is_honeypot=false"#, "fofa", "quake", MissingFieldPolicy::Keep).unwrap();
+        assert_eq!(query, r#"title:"a""#);
+        assert_eq!(conversion.unmapped_fields, ["is_honeypot"]);
     }
 }
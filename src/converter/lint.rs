@@ -0,0 +1,277 @@
+use crate::config::{FieldType, PlatformConfig, QueryLintRule};
+use crate::converter::{Clause, Expr, QueryOptimizer, QueryRenderer};
+use std::fmt;
+
+/// Warning about a valid query that probably does not match what was meant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryLint {
+    pub rule: QueryLintRule,
+    pub message: String,
+}
+
+impl fmt::Display for QueryLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]", self.message, self.rule)
+    }
+}
+
+/// Checker reporting suspicious queries with the [`QueryLintRule`]s a platform enables
+///
+/// Fields of type number or boolean hold a single value per record, so two
+/// different values of such a field cannot both match.
+pub struct QueryLinter;
+
+impl QueryLinter {
+    /// Check a parsed query of a platform
+    pub fn lint(expr: &Expr, config: &PlatformConfig) -> Vec<QueryLint> {
+        let expr = QueryOptimizer::optimize(expr.clone());
+        let mut lints = Vec::new();
+
+        Self::check_operands(&expr, false, config, &mut lints);
+        Self::check_broad_search(&expr, config, &mut lints);
+        Self::check_values(&expr, config, &mut lints);
+
+        lints.retain(|lint| config.lint_enabled(lint.rule));
+        lints.sort_by_key(|lint| lint.rule);
+        lints
+    }
+
+    /// Compare the clauses combined by each `AND` and `OR`, `negate`d under an odd number of `NOT`s
+    fn check_operands(expr: &Expr, negate: bool, config: &PlatformConfig, lints: &mut Vec<QueryLint>) {
+        let children = match expr {
+            Expr::Not(inner) | Expr::Group(inner) => {
+                return Self::check_operands(inner, negate ^ matches!(expr, Expr::Not(_)), config, lints)
+            }
+            Expr::And(children) | Expr::Or(children) => children,
+            _ => return,
+        };
+        // Under a negation an `AND` of the clauses is an `OR` of their negations
+        let conjunction = matches!(expr, Expr::And(_)) != negate;
+
+        let clauses: Vec<(&Clause, bool)> = children
+            .iter()
+            .filter_map(|child| match child {
+                Expr::Clause(clause) => Some((clause, clause.negated != negate)),
+                _ => None,
+            })
+            .collect();
+
+        for (i, &(a, a_negated)) in clauses.iter().enumerate() {
+            for &(b, b_negated) in &clauses[i + 1..] {
+                let (Some(field), Some(other)) = (config.resolve_field(&a.field), config.resolve_field(&b.field)) else {
+                    continue;
+                };
+                if field.0 != other.0 {
+                    continue;
+                }
+                let single_valued = matches!(field.1.field_type, FieldType::Number | FieldType::Boolean);
+                let (a_text, b_text) = (Self::clause_text(a, a_negated, config), Self::clause_text(b, b_negated, config));
+
+                if a.value.text == b.value.text {
+                    if a_negated == b_negated {
+                        continue;
+                    }
+                    lints.push(if conjunction {
+                        QueryLint {
+                            rule: QueryLintRule::Contradiction,
+                            message: format!("{} and {} cannot both match", a_text, b_text),
+                        }
+                    } else {
+                        QueryLint {
+                            rule: QueryLintRule::Tautology,
+                            message: format!("{} or {} matches every record", a_text, b_text),
+                        }
+                    });
+                } else if single_valued && conjunction && !a_negated && !b_negated {
+                    lints.push(QueryLint {
+                        rule: QueryLintRule::Contradiction,
+                        message: format!(
+                            "{} and {} cannot both match, as '{}' holds one value per record",
+                            a_text, b_text, a.field
+                        ),
+                    });
+                } else if single_valued && !conjunction && a_negated && b_negated {
+                    lints.push(QueryLint {
+                        rule: QueryLintRule::OrOfNegations,
+                        message: format!(
+                            "{} or {} matches every record, as '{}' holds one value per record",
+                            a_text, b_text, a.field
+                        ),
+                    });
+                }
+            }
+        }
+
+        for child in children {
+            Self::check_operands(child, negate, config, lints);
+        }
+    }
+
+    /// Warn when every clause a record must match is on a `broad` field
+    fn check_broad_search(expr: &Expr, config: &PlatformConfig, lints: &mut Vec<QueryLint>) {
+        if Self::has_term(expr) {
+            return;
+        }
+
+        let mut fields: Vec<&str> = Vec::new();
+        for (clause, negated) in expr.clauses() {
+            if negated {
+                continue;
+            }
+            match config.resolve_field(&clause.field) {
+                Some((_, spec)) if spec.broad => {
+                    if !fields.contains(&clause.field.as_str()) {
+                        fields.push(&clause.field);
+                    }
+                }
+                _ => return,
+            }
+        }
+
+        if !fields.is_empty() {
+            lints.push(QueryLint {
+                rule: QueryLintRule::BroadSearch,
+                message: format!(
+                    "The query only matches on '{}', which matches many records; narrow it with other fields",
+                    fields.join("', '")
+                ),
+            });
+        }
+    }
+
+    /// Warn about values the engine may not match as written
+    fn check_values(expr: &Expr, config: &PlatformConfig, lints: &mut Vec<QueryLint>) {
+        let mut seen: Vec<&Clause> = Vec::new();
+        for (clause, _) in expr.clauses() {
+            if seen.iter().any(|other| other.field == clause.field && other.value == clause.value) {
+                continue;
+            }
+            seen.push(clause);
+            let Some((_, spec)) = config.resolve_field(&clause.field) else {
+                continue;
+            };
+
+            let text = &clause.value.text;
+            if !clause.value.quoted
+                && spec.field_type == FieldType::String
+                && text.chars().any(|c| !c.is_alphanumeric() && c != '_')
+            {
+                lints.push(QueryLint {
+                    rule: QueryLintRule::UnquotedValue,
+                    message: format!(
+                        "Value '{}' of '{}' is not quoted and may be split into several words; quote it",
+                        text, clause.field
+                    ),
+                });
+            }
+            if spec.case_sensitive && text.chars().any(char::is_alphabetic) {
+                lints.push(QueryLint {
+                    rule: QueryLintRule::CaseSensitive,
+                    message: format!(
+                        "'{}' is case-sensitive, so '{}' does not match other capitalizations",
+                        clause.field, text
+                    ),
+                });
+            }
+        }
+    }
+
+    /// Check whether the expression contains a search term
+    fn has_term(expr: &Expr) -> bool {
+        match expr {
+            Expr::Term(_) => true,
+            Expr::Clause(_) => false,
+            Expr::Not(inner) | Expr::Group(inner) => Self::has_term(inner),
+            Expr::And(children) | Expr::Or(children) => children.iter().any(Self::has_term),
+        }
+    }
+
+    /// Render a clause with its effective negation in the syntax of the platform
+    fn clause_text(clause: &Clause, negated: bool, config: &PlatformConfig) -> String {
+        let clause = Clause {
+            negated,
+            ..clause.clone()
+        };
+        QueryRenderer::render_clause(&clause, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigManager, EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+    use crate::converter::QueryParser;
+
+    fn fofa() -> PlatformConfig {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        manager.get_platform_config("fofa").unwrap().clone()
+    }
+
+    fn rules(query: &str, config: &PlatformConfig) -> Vec<QueryLintRule> {
+        let expr = QueryParser::parse(query, config).unwrap();
+        QueryLinter::lint(&expr, config).into_iter().map(|lint| lint.rule).collect()
+    }
+
+    #[test]
+    fn contradictions_need_values_that_cannot_match_together() {
+        let config = fofa();
+        assert_eq!(rules(r#"port="80" && port="443""#, &config), [QueryLintRule::Contradiction]);
+        assert_eq!(rules(r#"title="a" && title!="a""#, &config), [QueryLintRule::Contradiction]);
+        assert!(rules(r#"title="a" && title="b""#, &config).is_empty());
+        assert!(rules(r#"port="80" || port="443""#, &config).is_empty());
+    }
+
+    #[test]
+    fn tautologies_need_a_clause_and_its_negation() {
+        let config = fofa();
+        assert_eq!(rules(r#"title="a" || title!="a""#, &config), [QueryLintRule::Tautology]);
+        assert!(rules(r#"title="a" || title!="b""#, &config).is_empty());
+    }
+
+    #[test]
+    fn or_of_negations_needs_a_single_valued_field() {
+        let config = fofa();
+        assert_eq!(rules(r#"port!="80" || port!="443""#, &config), [QueryLintRule::OrOfNegations]);
+        assert!(rules(r#"title!="a" || title!="b""#, &config).is_empty());
+    }
+
+    #[test]
+    fn broad_searches_match_only_on_broad_fields() {
+        let config = fofa();
+        assert_eq!(rules(r#"body="x""#, &config), [QueryLintRule::BroadSearch]);
+        assert!(rules(r#"body="x" && title="a""#, &config).is_empty());
+        assert!(rules(r#"body="x" && "login""#, &config).is_empty());
+    }
+
+    #[test]
+    fn unquoted_values_are_reported_when_they_may_be_split() {
+        let config = fofa();
+        assert_eq!(rules("title=a-b", &config), [QueryLintRule::UnquotedValue]);
+        assert!(rules("title=a_b", &config).is_empty());
+        assert!(rules(r#"title="a-b""#, &config).is_empty());
+    }
+
+    #[test]
+    fn case_sensitive_fields_report_values_with_letters() {
+        let mut config = fofa();
+        config.fields.get_mut("title").unwrap().case_sensitive = true;
+        assert_eq!(rules(r#"title="Login""#, &config), [QueryLintRule::CaseSensitive]);
+        assert!(rules(r#"title="404""#, &config).is_empty());
+        assert!(rules(r#"header="Login""#, &config).is_empty());
+    }
+
+    #[test]
+    fn disabled_rules_are_not_reported() {
+        let mut config = fofa();
+        config.query_lint.insert(QueryLintRule::Contradiction, false);
+        assert!(rules(r#"port="80" && port="443""#, &config).is_empty());
+        assert_eq!(rules(r#"port!="80" || port!="443""#, &config), [QueryLintRule::OrOfNegations]);
+    }
+
+    #[test]
+    fn nested_operands_are_compared_after_flattening() {
+        let config = fofa();
+        assert_eq!(rules(r#"port="80" && (title="a" && port="443")"#, &config), [QueryLintRule::Contradiction]);
+        assert!(rules(r#"port="80" && (title="a" || port="443")"#, &config).is_empty());
+    }
+}
@@ -1,9 +1,21 @@
 pub mod query;
-pub mod operators;
+pub mod ast;
+pub mod parser;
+pub mod renderer;
+pub mod optimizer;
+pub mod equivalence;
 pub mod fields;
 pub mod validator;
+pub mod lint;
+pub mod report;
 
 pub use query::*;
-pub use operators::*;
+pub use ast::*;
+pub use parser::*;
+pub use renderer::*;
+pub use optimizer::*;
+pub use equivalence::*;
 pub use fields::*;
 pub use validator::*;
+pub use lint::*;
+pub use report::*;
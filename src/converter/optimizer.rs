@@ -0,0 +1,220 @@
+use crate::config::PlatformConfig;
+use crate::converter::{Expr, QueryParser};
+use crate::error::{ConversionError, ConversionResult, OperatorKind};
+
+/// Simplification pass shortening queries without changing what they match
+pub struct QueryOptimizer;
+
+impl QueryOptimizer {
+    /// Simplify an expression and adapt its negations to a platform
+    ///
+    /// Platforms without a prefix `NOT` get their negations pushed down to the
+    /// clauses with De Morgan's laws, then the result is simplified again.
+    pub fn optimize_for(expr: Expr, config: &PlatformConfig, platform_name: &str) -> ConversionResult<Expr> {
+        let expr = Self::optimize(expr);
+        if QueryParser::has_prefix_not(&config.operators) {
+            return Ok(expr);
+        }
+        Ok(Self::optimize(Self::push_negations(expr, false, config, platform_name)?))
+    }
+
+    /// Simplify an expression
+    ///
+    /// - Parentheses are removed; the renderer adds back those the precedence needs.
+    /// - Nested `AND`s and `OR`s are flattened.
+    /// - Double negations cancel out, and a negated clause absorbs its `NOT`.
+    /// - Repeated operands of an `AND` or `OR` are removed.
+    /// - Operands implied by another operand are absorbed, e.g. `a OR (a AND b)` is `a`.
+    pub fn optimize(expr: Expr) -> Expr {
+        match expr {
+            Expr::Group(inner) => Self::optimize(*inner),
+            Expr::Not(inner) => match Self::optimize(*inner) {
+                Expr::Not(operand) => *operand,
+                Expr::Clause(mut clause) => {
+                    clause.negated = !clause.negated;
+                    Expr::Clause(clause)
+                }
+                operand => Expr::Not(Box::new(operand)),
+            },
+            Expr::And(children) => Self::combine(children, true),
+            Expr::Or(children) => Self::combine(children, false),
+            other => other,
+        }
+    }
+
+    /// Remove `NOT` nodes by negating the clauses below them
+    pub fn push_negations(
+        expr: Expr,
+        negate: bool,
+        config: &PlatformConfig,
+        platform_name: &str,
+    ) -> ConversionResult<Expr> {
+        let push_all = |children: Vec<Expr>| {
+            children
+                .into_iter()
+                .map(|child| Self::push_negations(child, negate, config, platform_name))
+                .collect::<ConversionResult<Vec<_>>>()
+        };
+
+        Ok(match expr {
+            // The platform negates with `!=`, which has no exact form
+            Expr::Clause(clause) if clause.exact && clause.negated != negate => {
+                return Err(ConversionError::UnsupportedOperator {
+                    platform: platform_name.to_string(),
+                    kind: OperatorKind::Negation,
+                    operator: format!("NOT field{}value", config.operators.exact.as_deref().unwrap_or("==")),
+                    suggestion: format!("field{}value", config.operators.not_equal),
+                    span: None,
+                });
+            }
+            Expr::Clause(mut clause) => {
+                clause.negated ^= negate;
+                Expr::Clause(clause)
+            }
+            Expr::Term(_) if negate => {
+                return Err(ConversionError::UnsupportedOperator {
+                    platform: platform_name.to_string(),
+                    kind: OperatorKind::Negation,
+                    operator: "NOT".to_string(),
+                    suggestion: format!("field{}value", config.operators.not_equal),
+                    span: None,
+                });
+            }
+            Expr::Term(value) => Expr::Term(value),
+            Expr::Not(inner) => Self::push_negations(*inner, !negate, config, platform_name)?,
+            Expr::And(children) if negate => Expr::Or(push_all(children)?),
+            Expr::And(children) => Expr::And(push_all(children)?),
+            Expr::Or(children) if negate => Expr::And(push_all(children)?),
+            Expr::Or(children) => Expr::Or(push_all(children)?),
+            Expr::Group(inner) => Expr::Group(Box::new(Self::push_negations(*inner, negate, config, platform_name)?)),
+        })
+    }
+
+    /// Simplify the operands of an `AND` (`is_and`) or `OR` and combine them again
+    fn combine(children: Vec<Expr>, is_and: bool) -> Expr {
+        let mut operands: Vec<Expr> = Vec::new();
+        for child in children {
+            let flattened = match Self::optimize(child) {
+                Expr::And(nested) if is_and => nested,
+                Expr::Or(nested) if !is_and => nested,
+                other => vec![other],
+            };
+            for operand in flattened {
+                if !operands.contains(&operand) {
+                    operands.push(operand);
+                }
+            }
+        }
+
+        // In `a OR (a AND b)` the second operand only matches what `a` matches, and
+        // dually for `a AND (a OR b)`; operands with the same parts keep the first
+        let parts: Vec<Vec<&Expr>> = operands.iter().map(|operand| Self::parts(operand, !is_and)).collect();
+        let absorbed: Vec<bool> = (0..operands.len())
+            .map(|i| {
+                (0..operands.len()).any(|j| {
+                    let subset = parts[j].iter().all(|part| parts[i].contains(part));
+                    let equal = subset && parts[i].len() == parts[j].len();
+                    j != i && subset && (!equal || j < i)
+                })
+            })
+            .collect();
+        let mut operands: Vec<Expr> = operands
+            .into_iter()
+            .zip(absorbed)
+            .filter(|(_, absorbed)| !absorbed)
+            .map(|(operand, _)| operand)
+            .collect();
+
+        match operands.len() {
+            1 => operands.remove(0),
+            _ if is_and => Expr::And(operands),
+            _ => Expr::Or(operands),
+        }
+    }
+
+    /// Operands of an `AND` (`of_and`) or `OR`, or the expression itself if it is neither
+    fn parts(expr: &Expr, of_and: bool) -> Vec<&Expr> {
+        match expr {
+            Expr::And(children) if of_and => children.iter().collect(),
+            Expr::Or(children) if !of_and => children.iter().collect(),
+            other => vec![other],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigManager, CANONICAL_PLATFORM, EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+    use crate::converter::{Equivalence, EquivalenceChecker, QueryRenderer};
+
+    /// Optimize a query for a platform, checking that the result matches the same records
+    fn optimize(query: &str, platform: &str) -> String {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        let config = manager.get_platform_config(platform).unwrap();
+        let expr = QueryParser::parse(query, config).unwrap();
+        let optimized = QueryOptimizer::optimize_for(expr.clone(), config, platform).unwrap();
+        assert_eq!(
+            EquivalenceChecker::check(&expr, config, &optimized, config).unwrap(),
+            Equivalence::Equal,
+            "{} changed meaning",
+            query
+        );
+        QueryRenderer::render(&optimized, config, platform).unwrap()
+    }
+
+    #[test]
+    fn nested_operators_are_flattened() {
+        assert_eq!(optimize("a=1 AND (b=2 AND (c=3 AND d=4))", CANONICAL_PLATFORM), "a=1 AND b=2 AND c=3 AND d=4");
+        assert_eq!(optimize("((a=1 OR b=2)) OR c=3", CANONICAL_PLATFORM), "a=1 OR b=2 OR c=3");
+        assert_eq!(optimize("a=1 AND (b=2 OR c=3)", CANONICAL_PLATFORM), "a=1 AND (b=2 OR c=3)");
+    }
+
+    #[test]
+    fn implied_operands_are_absorbed() {
+        assert_eq!(optimize("a=1 OR (a=1 AND b=2)", CANONICAL_PLATFORM), "a=1");
+        assert_eq!(optimize("a=1 AND (a=1 OR b=2)", CANONICAL_PLATFORM), "a=1");
+        assert_eq!(optimize("(a=1 AND b=2) OR (b=2 AND a=1 AND c=3)", CANONICAL_PLATFORM), "a=1 AND b=2");
+    }
+
+    #[test]
+    fn repeated_operands_are_removed() {
+        assert_eq!(optimize("a=1 AND b=2 AND a=1", CANONICAL_PLATFORM), "a=1 AND b=2");
+        assert_eq!(optimize("(a=1 OR b=2) AND (a=1 OR b=2)", CANONICAL_PLATFORM), "a=1 OR b=2");
+        // The same value negated is a different operand
+        assert_eq!(optimize("a=1 OR NOT a=1", CANONICAL_PLATFORM), "a=1 OR NOT a=1");
+    }
+
+    #[test]
+    fn double_negations_cancel_out() {
+        assert_eq!(optimize("NOT NOT a=1", CANONICAL_PLATFORM), "a=1");
+        assert_eq!(optimize("NOT (NOT (a=1 OR b=2))", CANONICAL_PLATFORM), "a=1 OR b=2");
+        assert_eq!(optimize("NOT NOT NOT a=1", CANONICAL_PLATFORM), "NOT a=1");
+    }
+
+    #[test]
+    fn negations_are_pushed_down_for_infix_platforms() {
+        assert_eq!(optimize(r#"NOT (a=1 OR b=2)"#, CANONICAL_PLATFORM), "NOT (a=1 OR b=2)");
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        let canonical = manager.get_platform_config(CANONICAL_PLATFORM).unwrap();
+        let fofa = manager.get_platform_config("fofa").unwrap();
+        for (query, expected) in [
+            ("NOT (title=\"a\" OR port=80)", "title!=\"a\" && port!=80"),
+            ("NOT (title=\"a\" AND (port=80 OR NOT port=443))", "title!=\"a\" || (port!=80 && port=443)"),
+        ] {
+            let expr = QueryParser::parse(query, canonical).unwrap();
+            let pushed = QueryOptimizer::optimize_for(expr.clone(), fofa, "fofa").unwrap();
+            assert_eq!(EquivalenceChecker::check(&expr, canonical, &pushed, fofa).unwrap(), Equivalence::Equal);
+            assert_eq!(QueryRenderer::render(&pushed, fofa, "fofa").unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn negated_terms_cannot_be_pushed_down() {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        let canonical = manager.get_platform_config(CANONICAL_PLATFORM).unwrap();
+        let expr = QueryParser::parse(r#"NOT ("nginx" OR title="a")"#, canonical).unwrap();
+        let result = QueryOptimizer::optimize_for(expr, manager.get_platform_config("fofa").unwrap(), "fofa");
+        assert!(matches!(result, Err(ConversionError::UnsupportedOperator { kind: OperatorKind::Negation, .. })));
+    }
+}
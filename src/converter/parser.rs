@@ -0,0 +1,562 @@
+use crate::config::{Operators, PlatformConfig};
+use crate::converter::{Clause, Expr, QueryValue};
+use crate::error::{ConversionError, ConversionResult, Span};
+
+/// Token of a platform query
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LeftParen,
+    RightParen,
+    And,
+    Or,
+    Not,
+    Clause(Clause),
+    Term(QueryValue),
+}
+
+/// Token of a query scanned without knowing its platform
+///
+/// Every operator spelling of the supported platforms is recognized, so the
+/// validator can name an operator the platform does not support.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ScannedToken {
+    /// `AND`, `OR` or `NOT` in upper case, or `&&` or `||`
+    Operator(String),
+    /// `field<op>value` with the operator as written: `=`, `==`, `:` or `!=`
+    Clause {
+        field: String,
+        field_span: Span,
+        operator: String,
+        operator_span: Span,
+        value: String,
+        value_span: Span,
+    },
+    /// Search term, parenthesis or any other character
+    Other,
+}
+
+/// Parser turning the query syntax of a platform into an [`Expr`]
+///
+/// The operators come from the platform configuration. Word operators such as
+/// `AND` are matched case-insensitively; `AND` binds tighter than `OR`. A
+/// word `not_equal` operator (Quake's `NOT`) is a prefix that also negates
+/// parenthesized groups, any other (FOFA's `!=`) sits between field and value.
+/// Values are double-quoted strings, ranges such as `[80 TO 443]`, or bare words.
+pub struct QueryParser<'a> {
+    query: &'a str,
+    operators: &'a Operators,
+    tokens: Vec<(Token, Span)>,
+    position: usize,
+}
+
+impl<'a> QueryParser<'a> {
+    /// Parse a query written for a platform
+    pub fn parse(query: &'a str, config: &'a PlatformConfig) -> ConversionResult<Expr> {
+        let mut parser = Self {
+            query,
+            operators: &config.operators,
+            tokens: Vec::new(),
+            position: 0,
+        };
+        parser.tokenize()?;
+
+        if parser.tokens.is_empty() {
+            return Err(Self::error("Query is empty", Span { start: 0, end: query.len() }));
+        }
+
+        let expr = parser.parse_or()?;
+        match parser.tokens.get(parser.position) {
+            Some((Token::RightParen, span)) => Err(Self::error("Unbalanced closing parenthesis", *span)),
+            Some((_, span)) => Err(Self::error(
+                &format!("Expected '{}' or '{}' before '{}'", parser.operators.and, parser.operators.or, &query[span.start..span.end]),
+                *span,
+            )),
+            None => Ok(expr),
+        }
+    }
+
+    /// Split a query into tokens in the syntax of any platform
+    ///
+    /// Unlike [`QueryParser::parse`] this never fails: malformed parts become
+    /// [`ScannedToken::Other`] and are reported by the parser.
+    pub(crate) fn scan(query: &str) -> Vec<(ScannedToken, Span)> {
+        let mut tokens = Vec::new();
+        let mut offset = 0;
+
+        while offset < query.len() {
+            let rest = &query[offset..];
+            let trimmed = rest.trim_start();
+            if trimmed.is_empty() {
+                break;
+            }
+            offset += rest.len() - trimmed.len();
+            let rest = trimmed;
+
+            let (token, length) = if let Some(symbol) = ["&&", "||"].into_iter().find(|symbol| rest.starts_with(symbol)) {
+                (ScannedToken::Operator(symbol.to_string()), symbol.len())
+            } else if rest.starts_with('"') {
+                (ScannedToken::Other, Self::read_quoted(rest, offset).map_or(rest.len(), |(_, length)| length))
+            } else if Self::field_name_length(rest) > 0 {
+                Self::scan_clause_or_word(rest, offset)
+            } else {
+                (ScannedToken::Other, rest.chars().next().map_or(1, char::len_utf8))
+            };
+            tokens.push((token, Span { start: offset, end: offset + length }));
+            offset += length;
+        }
+
+        tokens
+    }
+
+    /// Scan `field<op>value`, or a word that is a keyword or search term
+    fn scan_clause_or_word(input: &str, offset: usize) -> (ScannedToken, usize) {
+        let field_length = Self::field_name_length(input);
+        let field = &input[..field_length];
+        let after_field = &input[field_length..];
+        let operator_start = field_length + after_field.len() - after_field.trim_start().len();
+
+        let Some(operator) = ["!=", "==", "=", ":"].into_iter().find(|operator| input[operator_start..].starts_with(operator)) else {
+            let word = field.to_ascii_uppercase();
+            let token = match word.as_str() {
+                "AND" | "OR" | "NOT" => ScannedToken::Operator(word),
+                _ => ScannedToken::Other,
+            };
+            return (token, field_length);
+        };
+
+        let value_input = &input[operator_start + operator.len()..];
+        let value_start = input.len() - value_input.trim_start().len();
+        let value_input = &input[value_start..];
+        let (value, value_length) = if let Some(unterminated) = value_input.strip_prefix('"') {
+            match Self::read_quoted(value_input, offset + value_start) {
+                Ok((value, length)) => (value.text, length),
+                Err(_) => (unterminated.to_string(), value_input.len()),
+            }
+        } else if value_input.starts_with(['[', '{']) {
+            match Self::read_range(value_input, offset + value_start) {
+                Ok((value, length)) => (value.text, length),
+                Err(_) => (value_input.to_string(), value_input.len()),
+            }
+        } else {
+            let length = value_input
+                .find(|c: char| c.is_whitespace() || c == ')')
+                .unwrap_or(value_input.len());
+            (value_input[..length].to_string(), length)
+        };
+        // The span of a quoted value excludes its quotes
+        let value_start_offset = offset + value_start;
+        let value_span = if !value_input.starts_with('"') {
+            Span { start: value_start_offset, end: value_start_offset + value_length }
+        } else if value_input[1..value_length].ends_with('"') {
+            Span { start: value_start_offset + 1, end: value_start_offset + value_length - 1 }
+        } else {
+            Span { start: value_start_offset + 1, end: value_start_offset + value_length }
+        };
+
+        let token = ScannedToken::Clause {
+            field: field.to_string(),
+            field_span: Span { start: offset, end: offset + field_length },
+            operator: operator.to_string(),
+            operator_span: Span { start: offset + operator_start, end: offset + operator_start + operator.len() },
+            value,
+            value_span,
+        };
+        (token, value_start + value_length)
+    }
+
+    /// Check whether the platform negates with a prefix keyword rather than an infix operator
+    pub fn has_prefix_not(operators: &Operators) -> bool {
+        operators.not_equal.chars().all(char::is_alphabetic)
+    }
+
+    fn parse_or(&mut self) -> ConversionResult<Expr> {
+        let mut operands = vec![self.parse_and()?];
+        while self.accept(&Token::Or) {
+            operands.push(self.parse_and()?);
+        }
+        Ok(if operands.len() == 1 { operands.remove(0) } else { Expr::Or(operands) })
+    }
+
+    fn parse_and(&mut self) -> ConversionResult<Expr> {
+        let mut operands = vec![self.parse_unary()?];
+        while self.accept(&Token::And) {
+            operands.push(self.parse_unary()?);
+        }
+        Ok(if operands.len() == 1 { operands.remove(0) } else { Expr::And(operands) })
+    }
+
+    fn parse_unary(&mut self) -> ConversionResult<Expr> {
+        if !self.accept(&Token::Not) {
+            return self.parse_primary();
+        }
+
+        Ok(match self.parse_unary()? {
+            // `NOT field:value` is the negated clause itself
+            Expr::Clause(mut clause) if !clause.negated => {
+                clause.negated = true;
+                Expr::Clause(clause)
+            }
+            operand => Expr::Not(Box::new(operand)),
+        })
+    }
+
+    fn parse_primary(&mut self) -> ConversionResult<Expr> {
+        let Some((token, span)) = self.tokens.get(self.position).cloned() else {
+            return Err(Self::error("Query ends where a clause was expected", Span {
+                start: self.query.len(),
+                end: self.query.len(),
+            }));
+        };
+        self.position += 1;
+
+        match token {
+            Token::Clause(clause) => Ok(Expr::Clause(clause)),
+            Token::Term(value) => Ok(Expr::Term(value)),
+            Token::LeftParen => {
+                let inner = self.parse_or()?;
+                if !self.accept(&Token::RightParen) {
+                    return Err(Self::error("Unbalanced opening parenthesis", span));
+                }
+                Ok(Expr::Group(Box::new(inner)))
+            }
+            _ => Err(Self::error(
+                &format!("Expected a clause before '{}'", &self.query[span.start..span.end]),
+                span,
+            )),
+        }
+    }
+
+    /// Consume the next token if it equals the expected one
+    fn accept(&mut self, expected: &Token) -> bool {
+        match self.tokens.get(self.position) {
+            Some((token, _)) if token == expected => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Split the query into tokens
+    fn tokenize(&mut self) -> ConversionResult<()> {
+        let query = self.query;
+        let prefix_not = Self::has_prefix_not(self.operators);
+        let mut offset = 0;
+
+        while offset < query.len() {
+            let rest = &query[offset..];
+            let trimmed = rest.trim_start();
+            if trimmed.is_empty() {
+                break;
+            }
+            offset += rest.len() - trimmed.len();
+            let rest = trimmed;
+
+            let symbols = [
+                (&self.operators.left_paren, Token::LeftParen),
+                (&self.operators.right_paren, Token::RightParen),
+                (&self.operators.and, Token::And),
+                (&self.operators.or, Token::Or),
+            ];
+            let symbol = symbols
+                .into_iter()
+                .chain(prefix_not.then_some((&self.operators.not_equal, Token::Not)))
+                .find_map(|(operator, token)| Self::match_operator(rest, operator).map(|length| (token, length)));
+            if let Some((token, length)) = symbol {
+                self.tokens.push((token, Span { start: offset, end: offset + length }));
+                offset += length;
+                continue;
+            }
+
+            let (token, length) = if rest.starts_with('"') {
+                let (value, length) = Self::read_quoted(rest, offset)?;
+                (Token::Term(value), length)
+            } else {
+                self.read_clause_or_word(rest, offset, prefix_not)?
+            };
+            self.tokens.push((token, Span { start: offset, end: offset + length }));
+            offset += length;
+        }
+
+        Ok(())
+    }
+
+    /// Length of an operator at the start of the input, if present
+    ///
+    /// Word operators match case-insensitively and only as whole words.
+    fn match_operator(input: &str, operator: &str) -> Option<usize> {
+        if operator.is_empty() || input.len() < operator.len() || !input.is_char_boundary(operator.len()) {
+            return None;
+        }
+
+        let candidate = &input[..operator.len()];
+        if operator.chars().all(char::is_alphabetic) {
+            let at_word_end = !input[operator.len()..].starts_with(Self::is_word_char);
+            (candidate.eq_ignore_ascii_case(operator) && at_word_end).then_some(operator.len())
+        } else {
+            (candidate == operator).then_some(operator.len())
+        }
+    }
+
+    /// Read `field<op>value`, or a bare search word when no operator follows
+    fn read_clause_or_word(&self, input: &str, offset: usize, prefix_not: bool) -> ConversionResult<(Token, usize)> {
+        let field_length = Self::field_name_length(input);
+        if field_length == 0 {
+            let length = input.chars().next().map_or(1, char::len_utf8);
+            return Err(Self::error(
+                &format!("Unexpected '{}'", &input[..length]),
+                Span { start: offset, end: offset + length },
+            ));
+        }
+
+        let field = &input[..field_length];
+        let after_field = &input[field_length..];
+        let operator_start = after_field.len() - after_field.trim_start().len();
+        let after_space = &after_field[operator_start..];
+
+        let exact_operator = self.operators.exact.as_deref().filter(|exact| after_space.starts_with(exact));
+        let operator = if !prefix_not && after_space.starts_with(self.operators.not_equal.as_str()) {
+            Some((true, false, self.operators.not_equal.len()))
+        } else if let Some(exact) = exact_operator {
+            Some((false, true, exact.len()))
+        } else if after_space.starts_with(self.operators.equal.as_str()) {
+            Some((false, false, self.operators.equal.len()))
+        } else {
+            None
+        };
+
+        let Some((negated, exact, operator_length)) = operator else {
+            // A bare word is a search term, unless it is a field missing its operator
+            let value = QueryValue { text: field.to_string(), quoted: false };
+            return Ok((Token::Term(value), field_length));
+        };
+
+        let value_offset = field_length + operator_start + operator_length;
+        let value_input = &input[value_offset..];
+        if !negated && !exact && value_input.starts_with(self.operators.equal.as_str()) {
+            let operator_offset = offset + field_length + operator_start;
+            return Err(Self::error(
+                &format!(
+                    "Exact match '{0}{0}' is not supported by this platform, use '{0}'",
+                    self.operators.equal
+                ),
+                Span { start: operator_offset, end: operator_offset + 2 * operator_length },
+            ));
+        }
+        let value_space = value_input.len() - value_input.trim_start().len();
+        let value_input = &value_input[value_space..];
+        let value_start = offset + value_offset + value_space;
+
+        let (value, value_length) = if value_input.starts_with('"') {
+            Self::read_quoted(value_input, value_start)?
+        } else if value_input.starts_with(['[', '{']) {
+            Self::read_range(value_input, value_start)?
+        } else {
+            let right_paren = self.operators.right_paren.as_str();
+            let length = value_input
+                .char_indices()
+                .find(|(i, c)| c.is_whitespace() || value_input[*i..].starts_with(right_paren))
+                .map_or(value_input.len(), |(i, _)| i);
+            if length == 0 {
+                return Err(Self::error(
+                    &format!("Missing value for field '{}'", field),
+                    Span { start: offset, end: value_start },
+                ));
+            }
+            (QueryValue { text: value_input[..length].to_string(), quoted: false }, length)
+        };
+
+        let clause = Clause {
+            field: field.to_string(),
+            negated,
+            exact,
+            value,
+        };
+        Ok((Token::Clause(clause), value_offset + value_space + value_length))
+    }
+
+    /// Length of a dotted field name at the start of the input
+    fn field_name_length(input: &str) -> usize {
+        let mut length = 0;
+        let mut previous_dot = true;
+        for (i, c) in input.char_indices() {
+            if Self::is_word_char(c) {
+                previous_dot = false;
+            } else if c == '.' && !previous_dot {
+                previous_dot = true;
+            } else {
+                break;
+            }
+            length = i + c.len_utf8();
+        }
+        // A trailing dot is not part of the name
+        if input[..length].ends_with('.') {
+            length -= 1;
+        }
+        length
+    }
+
+    /// Read a range such as `[80 TO 443]` or `{* TO 2024-01-01]` as a single unquoted value
+    fn read_range(input: &str, offset: usize) -> ConversionResult<(QueryValue, usize)> {
+        match input.find([']', '}']) {
+            Some(end) => Ok((QueryValue { text: input[..=end].to_string(), quoted: false }, end + 1)),
+            None => Err(Self::error("Unterminated range value", Span { start: offset, end: offset + input.len() })),
+        }
+    }
+
+    /// Read a double-quoted string, unescaping `\"` and `\\`
+    fn read_quoted(input: &str, offset: usize) -> ConversionResult<(QueryValue, usize)> {
+        let mut text = String::new();
+        let mut chars = input.char_indices().skip(1);
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((QueryValue { text, quoted: true }, i + 1)),
+                '\\' => match chars.next() {
+                    Some((_, escaped @ ('"' | '\\'))) => text.push(escaped),
+                    Some((_, other)) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                    None => break,
+                },
+                c => text.push(c),
+            }
+        }
+
+        Err(Self::error("Unterminated quoted value", Span { start: offset, end: offset + input.len() }))
+    }
+
+    fn is_word_char(c: char) -> bool {
+        c.is_alphanumeric() || c == '_'
+    }
+
+    fn error(message: &str, span: Span) -> ConversionError {
+        ConversionError::SyntaxValidationFailed {
+            message: message.to_string(),
+            span: Some(span),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigManager, EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+
+    fn parse(query: &str, platform: &str) -> ConversionResult<Expr> {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        QueryParser::parse(query, manager.get_platform_config(platform).unwrap())
+    }
+
+    fn clause(field: &str, negated: bool, text: &str, quoted: bool) -> Expr {
+        Expr::Clause(Clause {
+            field: field.to_string(),
+            negated,
+            exact: false,
+            value: QueryValue { text: text.to_string(), quoted },
+        })
+    }
+
+    fn error_span<'a>(query: &'a str, platform: &str) -> &'a str {
+        match parse(query, platform) {
+            Err(ConversionError::SyntaxValidationFailed { span: Some(span), .. }) => &query[span.start..span.end],
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse(r#"title="a" || port=80 && country="CN""#, "fofa").unwrap(),
+            Expr::Or(vec![
+                clause("title", false, "a", true),
+                Expr::And(vec![clause("port", false, "80", false), clause("country", false, "CN", true)]),
+            ])
+        );
+        assert_eq!(
+            parse(r#"(title="a" || port=80) && country="CN""#, "fofa").unwrap(),
+            Expr::And(vec![
+                Expr::Group(Box::new(Expr::Or(vec![
+                    clause("title", false, "a", true),
+                    clause("port", false, "80", false),
+                ]))),
+                clause("country", false, "CN", true),
+            ])
+        );
+    }
+
+    #[test]
+    fn prefix_not_negates_a_clause_or_a_group() {
+        assert_eq!(
+            parse(r#"not title:"a" AND port:80"#, "quake").unwrap(),
+            Expr::And(vec![clause("title", true, "a", true), clause("port", false, "80", false)])
+        );
+        assert_eq!(
+            parse(r#"NOT (title:"a" OR port:80)"#, "quake").unwrap(),
+            Expr::Not(Box::new(Expr::Group(Box::new(Expr::Or(vec![
+                clause("title", false, "a", true),
+                clause("port", false, "80", false),
+            ])))))
+        );
+        assert_eq!(
+            parse(r#"NOT NOT title:"a""#, "quake").unwrap(),
+            Expr::Not(Box::new(clause("title", true, "a", true)))
+        );
+    }
+
+    #[test]
+    fn infix_not_negates_the_clause() {
+        assert_eq!(
+            parse(r#"title!="a" && port=80"#, "fofa").unwrap(),
+            Expr::And(vec![clause("title", true, "a", true), clause("port", false, "80", false)])
+        );
+        // A platform with an infix negation has no `NOT` keyword, so the word is a search term
+        assert_eq!(
+            parse(r#"NOT && title="a""#, "fofa").unwrap(),
+            Expr::And(vec![Expr::Term(QueryValue { text: "NOT".to_string(), quoted: false }), clause("title", false, "a", true)])
+        );
+    }
+
+    #[test]
+    fn quoted_values_are_unescaped() {
+        assert_eq!(parse(r#"title="a\"b\\c\d""#, "fofa").unwrap(), clause("title", false, r#"a"b\c\d"#, true));
+        assert_eq!(parse(r#"title="x && y""#, "fofa").unwrap(), clause("title", false, "x && y", true));
+        assert_eq!(parse("port=[80 TO 443]", "fofa").unwrap(), clause("port", false, "[80 TO 443]", false));
+    }
+
+    #[test]
+    fn double_equals_is_an_exact_match_where_the_platform_has_one() {
+        let Expr::Clause(exact) = parse(r#"title == "a""#, "fofa").unwrap() else { panic!("not a clause") };
+        assert!(exact.exact && !exact.negated);
+        assert_eq!(exact.value, QueryValue::quoted("a"));
+        assert_eq!(parse(r#"title!="a""#, "fofa").unwrap(), clause("title", true, "a", true));
+        assert!(parse(r#"title=="a""#, "zoomeye").is_err());
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_text() {
+        assert_eq!(error_span(r#"port=80 && title="a"#, "fofa"), r#""a"#);
+        assert_eq!(error_span(r#"(title="a" || port=80"#, "fofa"), "(");
+        assert_eq!(error_span(r#"title="a" || port=80)"#, "fofa"), ")");
+        assert_eq!(error_span(r#"title="a" &&"#, "fofa"), "");
+        assert_eq!(error_span(r#"&& title="a""#, "fofa"), "&&");
+        assert_eq!(error_span(r#"title=="a""#, "zoomeye"), "==");
+        assert_eq!(error_span("port=[80 TO 443", "fofa"), "[80 TO 443");
+        assert_eq!(error_span("port=80 && title=", "fofa"), "title=");
+        assert_eq!(error_span(r#"title="a" port=80"#, "fofa"), "port=80");
+    }
+
+    #[test]
+    fn scan_ignores_operators_inside_values() {
+        let operators: Vec<_> = QueryParser::scan(r#"title:"x && y" and (port=[1 TO 2] || NOT a!=b)"#)
+            .into_iter()
+            .filter_map(|(token, _)| match token {
+                ScannedToken::Operator(operator) => Some(operator),
+                ScannedToken::Clause { operator, .. } => Some(operator),
+                ScannedToken::Other => None,
+            })
+            .collect();
+        assert_eq!(operators, [":", "AND", "=", "||", "NOT", "!="]);
+    }
+}
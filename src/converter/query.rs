@@ -1,20 +1,39 @@
-use crate::config::ConfigManager;
-use crate::converter::{FieldConverter, OperatorConverter, SyntaxValidator};
+use crate::config::{ConfigIssue, ConfigManager, IssueSeverity, MatchMode, MissingFieldPolicy};
+use crate::converter::{
+    ConversionReport, EncodedQuery, Equivalence, EquivalenceChecker, Expr, FieldConversion, FieldConverter, PlatformConversion, QueryOptimizer,
+    QueryLint, QueryParser, QueryRenderer, SyntaxValidator,
+};
 use crate::error::ConversionResult;
+use std::time::Instant;
 
 /// Main query converter
 pub struct QueryConverter {
     config_manager: ConfigManager,
+    missing_field_policy: MissingFieldPolicy,
 }
 
 impl QueryConverter {
     /// Create a new query converter with configuration manager
     pub fn new(config_manager: ConfigManager) -> Self {
-        Self { config_manager }
+        Self {
+            config_manager,
+            missing_field_policy: MissingFieldPolicy::default(),
+        }
+    }
+
+    /// Set the policy for fields without a target equivalent that do not set their own
+    pub fn with_missing_field_policy(mut self, policy: MissingFieldPolicy) -> Self {
+        self.missing_field_policy = policy;
+        self
+    }
+
+    /// Get the configuration manager backing this converter
+    pub fn config_manager(&self) -> &ConfigManager {
+        &self.config_manager
     }
 
     /// Validate query syntax for the source platform
-    pub fn validate_query_syntax(&self, query: &str, platform_name: &str) -> ConversionResult<()> {
+    pub fn validate_query_syntax(&self, query: &str, platform_name: &str) -> ConversionResult<Vec<QueryLint>> {
         let from_config = self.config_manager.get_platform_config(platform_name)?;
         SyntaxValidator::validate_query_syntax(query, from_config, platform_name)
     }
@@ -27,26 +46,183 @@ impl QueryConverter {
         to_platform: &str,
     ) -> ConversionResult<String> {
         let from_config = self.config_manager.get_platform_config(from_platform)?;
-        let to_config = self.config_manager.get_platform_config(to_platform)?;
+        self.config_manager.get_platform_config(to_platform)?;
 
         // If same platform, return directly
         if from_platform == to_platform {
             return Ok(query.to_string());
         }
 
-        // Normalize the query first (convert logical operators to uppercase)
-        let mut result = SyntaxValidator::normalize_query(query);
+        let expr = QueryParser::parse(query, from_config)?;
+        let (converted_query, _) = self.convert_expr(expr, from_platform, to_platform)?;
+        Ok(converted_query)
+    }
 
-        // Convert not equal operators first (needs to be done before field conversion)
-        result = OperatorConverter::convert_not_equal_operator(&result, from_config, to_config);
+    /// Convert a parsed query and render it for the target platform
+    fn convert_expr(
+        &self,
+        expr: Expr,
+        from_platform: &str,
+        to_platform: &str,
+    ) -> ConversionResult<(String, FieldConversion)> {
+        let from_config = self.config_manager.get_platform_config(from_platform)?;
+        let to_config = self.config_manager.get_platform_config(to_platform)?;
 
-        // Convert field prefixes
-        result = FieldConverter::convert_fields(&result, from_config, to_config);
+        let mut conversion = FieldConverter::convert_fields(expr, from_config, to_config, to_platform, self.missing_field_policy)?;
+        conversion.expr = QueryOptimizer::optimize_for(conversion.expr, to_config, to_platform)?;
+        let converted_query = QueryRenderer::render(&conversion.expr, to_config, to_platform)?;
+        Ok((converted_query, conversion))
+    }
 
-        // Convert other logical operators
-        result = OperatorConverter::convert_other_operators(&result, from_config, to_config);
+    /// Validate a query and render it simplified in canonical spacing for its own platform
+    ///
+    /// With `indented`, nested groups are laid out over several lines.
+    pub fn format(&self, query: &str, platform_name: &str, indented: bool) -> ConversionResult<String> {
+        self.validate_query_syntax(query, platform_name)?;
+        let config = self.config_manager.get_platform_config(platform_name)?;
 
-        Ok(result)
+        let expr = QueryOptimizer::optimize_for(QueryParser::parse(query, config)?, config, platform_name)?;
+        if indented {
+            QueryRenderer::render_indented(&expr, config, platform_name)
+        } else {
+            QueryRenderer::render(&expr, config, platform_name)
+        }
+    }
+
+    /// Validate two queries and check whether they match the same records
+    pub fn equivalence(
+        &self,
+        query_a: &str,
+        platform_a: &str,
+        query_b: &str,
+        platform_b: &str,
+    ) -> ConversionResult<Equivalence> {
+        self.validate_query_syntax(query_a, platform_a)?;
+        self.validate_query_syntax(query_b, platform_b)?;
+        let config_a = self.config_manager.get_platform_config(platform_a)?;
+        let config_b = self.config_manager.get_platform_config(platform_b)?;
+
+        EquivalenceChecker::check(
+            &QueryParser::parse(query_a, config_a)?,
+            config_a,
+            &QueryParser::parse(query_b, config_b)?,
+            config_b,
+        )
+    }
+
+    /// Validate a query and convert it to each of the target platforms
+    pub fn convert_all(
+        &self,
+        query: &str,
+        from_platform: &str,
+        target_platforms: &[String],
+    ) -> ConversionResult<ConversionReport> {
+        let started = Instant::now();
+
+        let warnings = self.validate_query_syntax(query, from_platform)?.iter().map(ToString::to_string).collect();
+        let from_config = self.config_manager.get_platform_config(from_platform)?;
+        let expr = QueryParser::parse(query, from_config)?;
+
+        let mut conversions = Vec::new();
+
+        for target_platform in target_platforms {
+            let to_config = self.config_manager.get_platform_config(target_platform)?;
+            let (converted_query, conversion) = self.convert_expr(expr.clone(), from_platform, target_platform)?;
+            let mut warnings = conversion.warnings;
+
+            // Warn where the target platform restricts how a converted field can be used
+            let mut vip_fields: Vec<&str> = Vec::new();
+            for (clause, negated) in conversion.expr.clauses() {
+                let Some((_, target_spec)) = to_config.resolve_field(&clause.field) else {
+                    continue;
+                };
+
+                if target_spec.vip && !vip_fields.contains(&clause.field.as_str()) {
+                    vip_fields.push(&clause.field);
+                    warnings.push(format!(
+                        "Field '{}' is only available to paid (VIP) accounts on {}",
+                        clause.field,
+                        target_platform.to_uppercase()
+                    ));
+                }
+
+                let mode = if negated { MatchMode::NotEqual } else { MatchMode::Equal };
+                if !target_spec.supports(mode) {
+                    warnings.push(format!(
+                        "Field '{}' does not support '{}' matching on {}",
+                        clause.field,
+                        mode,
+                        target_platform.to_uppercase()
+                    ));
+                } else if !target_spec.field_type.accepts(&clause.value.text) {
+                    warnings.push(format!(
+                        "Field '{}' expects a {} value on {}, got '{}'",
+                        clause.field,
+                        target_spec.field_type,
+                        target_platform.to_uppercase(),
+                        clause.value.text
+                    ));
+                }
+            }
+
+            conversions.push(PlatformConversion {
+                platform: target_platform.clone(),
+                encoded: EncodedQuery::new(&converted_query, to_config),
+                query: converted_query,
+                mapped_fields: conversion.mapped_fields,
+                unmapped_fields: conversion.unmapped_fields,
+                warnings,
+            });
+        }
+
+        Ok(ConversionReport {
+            source_platform: from_platform.to_string(),
+            original_query: query.to_string(),
+            warnings,
+            conversions,
+            elapsed_us: started.elapsed().as_micros() as u64,
+        })
+    }
+
+    /// Convert the test queries of every rewrite rule, reporting each mismatch as an error
+    pub fn check_rewrite_tests(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        for platform in self.get_supported_platforms() {
+            let Ok(config) = self.config_manager.get_platform_config(&platform) else {
+                continue;
+            };
+
+            for (target, rules) in &config.rewrites {
+                for (i, rule) in rules.iter().enumerate() {
+                    for (j, test) in rule.tests.iter().enumerate() {
+                        let converted = self
+                            .convert_all(&test.query, &platform, std::slice::from_ref(target))
+                            .map(|mut report| report.conversions.remove(0).query);
+                        let message = match converted {
+                            Ok(query) if query == test.expected => continue,
+                            Ok(query) => format!("Test '{}' converts to '{}', expected '{}'", test.query, query, test.expected),
+                            Err(e) => format!("Test '{}' does not convert: {}", test.query, e),
+                        };
+                        issues.push(ConfigIssue {
+                            severity: IssueSeverity::Error,
+                            location: format!("{}/rewrites/{}/{}/tests/{}", platform, target, i, j),
+                            message,
+                        });
+                    }
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Get all supported platforms except the given one
+    pub fn get_other_platforms(&self, platform: &str) -> Vec<String> {
+        self.get_supported_platforms()
+            .into_iter()
+            .filter(|other| other != platform)
+            .collect()
     }
 
     /// Get list of supported platforms
@@ -59,3 +235,36 @@ impl QueryConverter {
         self.config_manager.is_platform_supported(platform)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CANONICAL_PLATFORM, EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+
+    #[test]
+    fn embedded_rewrite_tests_pass() {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        let issues = QueryConverter::new(manager).check_rewrite_tests();
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn rewrite_tests_convert_the_same_through_the_canonical_platform() {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        let converter = QueryConverter::new(manager);
+        let convert = |query: &str, from: &str, to: &str| {
+            let mut report = converter.convert_all(query, from, &[to.to_string()]).unwrap();
+            report.conversions.remove(0).query
+        };
+
+        for platform in converter.get_supported_platforms() {
+            let config = converter.config_manager().get_platform_config(&platform).unwrap();
+            for (target, rules) in &config.rewrites {
+                for test in rules.iter().flat_map(|rule| &rule.tests) {
+                    let canonical = convert(&test.query, &platform, CANONICAL_PLATFORM);
+                    assert_eq!(convert(&canonical, CANONICAL_PLATFORM, target), test.expected, "{} via {}", test.query, canonical);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::config::PlatformConfig;
+use crate::converter::{Clause, Expr, QueryOptimizer, QueryParser};
+use crate::error::ConversionResult;
+
+/// Renderer writing an [`Expr`] in the query syntax of a platform
+///
+/// Operators are separated by single spaces. Parentheses are added wherever
+/// `AND` and `OR` are nested or negated, and groups left in the tree are
+/// written in parentheses too; conversion and `fmt` remove the parentheses of
+/// the input with [`QueryOptimizer::optimize`] before rendering. Platforms
+/// without a prefix `NOT` cannot negate groups, so negations are pushed down to
+/// the clauses with [`QueryOptimizer::push_negations`].
+pub struct QueryRenderer;
+
+/// Width in characters up to which a nested group stays on one line in the indented layout
+const LINE_WIDTH: usize = 80;
+
+/// Indentation of the operands of a broken group
+const INDENT: &str = "  ";
+
+impl QueryRenderer {
+    /// Render an expression for a platform
+    pub fn render(expr: &Expr, config: &PlatformConfig, platform_name: &str) -> ConversionResult<String> {
+        let mut output = String::new();
+        if QueryParser::has_prefix_not(&config.operators) {
+            Self::write(expr, config, false, &mut output);
+        } else {
+            let expr = QueryOptimizer::push_negations(expr.clone(), false, config, platform_name)?;
+            Self::write(&expr, config, false, &mut output);
+        }
+        Ok(output)
+    }
+
+    /// Render a single clause for a platform
+    pub fn render_clause(clause: &Clause, config: &PlatformConfig) -> String {
+        Self::inline(&Expr::Clause(clause.clone()), config, false)
+    }
+
+    /// Render an expression for a platform over several lines
+    ///
+    /// Each operand of the outermost `AND` or `OR` starts a line led by the
+    /// operator. A nested group stays on one line if it fits in 80 columns and
+    /// holds no group itself; otherwise its operands are indented on lines of
+    /// their own, and so on for the groups inside it.
+    pub fn render_indented(expr: &Expr, config: &PlatformConfig, platform_name: &str) -> ConversionResult<String> {
+        let lines = if QueryParser::has_prefix_not(&config.operators) {
+            Self::lines(expr, config, 0, 0)
+        } else {
+            let expr = QueryOptimizer::push_negations(expr.clone(), false, config, platform_name)?;
+            Self::lines(&expr, config, 0, 0)
+        };
+        Ok(lines.join("\n"))
+    }
+
+    /// Lines of an expression, without the indentation of the lines
+    ///
+    /// The lines are indented by `indent` columns, and the first one starts
+    /// `offset` columns further after the text already written on it.
+    fn lines(expr: &Expr, config: &PlatformConfig, indent: usize, offset: usize) -> Vec<String> {
+        let operators = &config.operators;
+
+        match expr {
+            Expr::And(children) | Expr::Or(children) => {
+                let separator = match expr {
+                    Expr::And(_) => &operators.and,
+                    _ => &operators.or,
+                };
+                let mut lines = Vec::new();
+                for (i, child) in children.iter().enumerate() {
+                    let prefix = if i > 0 { format!("{} ", separator) } else { String::new() };
+                    let first = if i > 0 { 0 } else { offset };
+                    let mut child_lines = Self::operand_lines(child, config, indent, first + prefix.chars().count());
+                    child_lines[0] = format!("{}{}", prefix, child_lines[0]);
+                    lines.extend(child_lines);
+                }
+                lines
+            }
+            Expr::Not(inner) => {
+                let prefix = format!("{} ", operators.not_equal);
+                let mut lines = Self::operand_lines(inner, config, indent, offset + prefix.chars().count());
+                lines[0] = format!("{}{}", prefix, lines[0]);
+                lines
+            }
+            Expr::Group(inner) => Self::operand_lines(inner, config, indent, offset),
+            _ => vec![Self::inline(expr, config, false)],
+        }
+    }
+
+    /// Lines of an operand, in parentheses if it is an `AND` or `OR`
+    fn operand_lines(expr: &Expr, config: &PlatformConfig, indent: usize, offset: usize) -> Vec<String> {
+        if !matches!(expr, Expr::And(_) | Expr::Or(_) | Expr::Group(_)) {
+            return Self::lines(expr, config, indent, offset);
+        }
+
+        let inner = match expr {
+            Expr::Group(inner) => inner,
+            _ => expr,
+        };
+        let inline = Self::inline(expr, config, true);
+        if indent + offset + inline.chars().count() <= LINE_WIDTH && !Self::has_group(inner) {
+            return vec![inline];
+        }
+        let mut lines = vec![config.operators.left_paren.clone()];
+        lines.extend(
+            Self::lines(inner, config, indent + INDENT.len(), 0)
+                .into_iter()
+                .map(|line| format!("{}{}", INDENT, line)),
+        );
+        lines.push(config.operators.right_paren.clone());
+        lines
+    }
+
+    /// Check whether an `AND` or `OR` has an operand that is itself an `AND` or `OR`, possibly negated
+    fn has_group(expr: &Expr) -> bool {
+        fn is_compound(expr: &Expr) -> bool {
+            match expr {
+                Expr::Not(inner) | Expr::Group(inner) => is_compound(inner),
+                Expr::And(_) | Expr::Or(_) => true,
+                Expr::Clause(_) | Expr::Term(_) => false,
+            }
+        }
+
+        match expr {
+            Expr::And(children) | Expr::Or(children) => children.iter().any(is_compound),
+            Expr::Not(inner) | Expr::Group(inner) => Self::has_group(inner),
+            Expr::Clause(_) | Expr::Term(_) => false,
+        }
+    }
+
+    /// Render an expression on one line
+    fn inline(expr: &Expr, config: &PlatformConfig, parenthesize: bool) -> String {
+        let mut output = String::new();
+        Self::write(expr, config, parenthesize, &mut output);
+        output
+    }
+
+    /// Append an expression, in parentheses when `parenthesize` is set and it is a compound
+    fn write(expr: &Expr, config: &PlatformConfig, parenthesize: bool, output: &mut String) {
+        let operators = &config.operators;
+
+        match expr {
+            Expr::Clause(clause) => {
+                // The converter only leaves clauses exact where the platform has an exact match
+                let equal = match &operators.exact {
+                    Some(exact) if clause.exact => exact,
+                    _ => &operators.equal,
+                };
+                if !clause.negated {
+                    output.push_str(&format!("{}{}{}", clause.field, equal, clause.value));
+                } else if QueryParser::has_prefix_not(operators) {
+                    output.push_str(&format!("{} {}{}{}", operators.not_equal, clause.field, equal, clause.value));
+                } else {
+                    output.push_str(&format!("{}{}{}", clause.field, operators.not_equal, clause.value));
+                }
+            }
+            Expr::Term(value) => output.push_str(&value.to_string()),
+            Expr::Not(inner) => {
+                output.push_str(&operators.not_equal);
+                output.push(' ');
+                Self::write(inner, config, true, output);
+            }
+            Expr::And(children) | Expr::Or(children) => {
+                let separator = match expr {
+                    Expr::And(_) => &operators.and,
+                    _ => &operators.or,
+                };
+                if parenthesize {
+                    output.push_str(&operators.left_paren);
+                }
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        output.push_str(&format!(" {} ", separator));
+                    }
+                    // Engines do not document the precedence of AND over OR, so never rely on it
+                    Self::write(child, config, matches!(child, Expr::And(_) | Expr::Or(_)), output);
+                }
+                if parenthesize {
+                    output.push_str(&operators.right_paren);
+                }
+            }
+            Expr::Group(inner) => {
+                output.push_str(&operators.left_paren);
+                Self::write(inner, config, false, output);
+                output.push_str(&operators.right_paren);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigManager, EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+
+    /// Render a query in the indented layout, checking that it parses back to the same query
+    fn indented(query: &str, platform: &str) -> String {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        let config = manager.get_platform_config(platform).unwrap();
+        let expr = QueryOptimizer::optimize(QueryParser::parse(query, config).unwrap());
+        let rendered = QueryRenderer::render_indented(&expr, config, platform).unwrap();
+        let reparsed = QueryOptimizer::optimize(QueryParser::parse(&rendered, config).unwrap());
+        assert_eq!(reparsed, expr, "{} does not parse back", rendered);
+        rendered
+    }
+
+    #[test]
+    fn groups_holding_groups_are_broken() {
+        assert_eq!(
+            indented(r#"title="a" && (port="80" || (port="443" && country="CN"))"#, "fofa"),
+            "title=\"a\"\n&& (\n  port=\"80\"\n  || (port=\"443\" && country=\"CN\")\n)"
+        );
+        assert_eq!(
+            indented(r#"title:"a" AND NOT (port:80 OR (port:443 AND NOT (country:"CN" OR country:"US")))"#, "quake"),
+            "title:\"a\"\nAND NOT (\n  port:80\n  OR (\n    port:443\n    AND NOT (country:\"CN\" OR country:\"US\")\n  )\n)"
+        );
+    }
+
+    #[test]
+    fn flat_groups_stay_on_one_line_while_they_fit() {
+        assert_eq!(indented(r#"title="a" && (port="80" || port="443")"#, "fofa"), "title=\"a\"\n&& (port=\"80\" || port=\"443\")");
+        assert_eq!(indented(r#"title="a""#, "fofa"), "title=\"a\"");
+
+        let countries: Vec<String> = (0..12).map(|i| format!("country=\"C{}\"", i)).collect();
+        let rendered = indented(&format!("title=\"a\" && ({})", countries.join(" || ")), "fofa");
+        assert!(rendered.lines().all(|line| line.chars().count() <= LINE_WIDTH), "{}", rendered);
+        assert_eq!(rendered.lines().count(), 2 + 1 + countries.len());
+    }
+
+    #[test]
+    fn widths_count_characters_not_bytes() {
+        // 65 characters, but more than 80 bytes in UTF-8
+        let title = "网".repeat(20);
+        let query = format!(r#"port="80" && (title="{0}" || body="{0}")"#, title);
+        assert_eq!(indented(&query, "fofa"), format!("port=\"80\"\n&& (title=\"{0}\" || body=\"{0}\")", title));
+    }
+}
@@ -0,0 +1,73 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use crate::config::PlatformConfig;
+
+/// Result of converting one query to several target platforms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversionReport {
+    pub source_platform: String,
+    pub original_query: String,
+    /// Lint warnings about the original query
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    pub conversions: Vec<PlatformConversion>,
+    /// Time spent validating and converting, in microseconds
+    pub elapsed_us: u64,
+}
+
+/// Converted query for a single target platform
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlatformConversion {
+    pub platform: String,
+    pub query: String,
+    pub mapped_fields: Vec<FieldMapping>,
+    pub unmapped_fields: Vec<String>,
+    pub warnings: Vec<String>,
+    pub encoded: EncodedQuery,
+}
+
+/// Source field renamed to its target platform equivalent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldMapping {
+    /// Canonical field name from the configuration
+    pub field: String,
+    pub source: String,
+    pub target: String,
+}
+
+/// Encoded forms of a converted query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodedQuery {
+    pub base64: String,
+    pub url: String,
+    /// Search page URL, when the platform configures a `search_url` template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_url: Option<String>,
+}
+
+impl ConversionReport {
+    /// Get the converted query for a target platform
+    pub fn get(&self, platform: &str) -> Option<&PlatformConversion> {
+        self.conversions.iter().find(|conversion| conversion.platform == platform)
+    }
+}
+
+impl EncodedQuery {
+    /// Encode a query, filling the platform's search URL template if present
+    ///
+    /// The template may use `{query}` for the URL-encoded query and
+    /// `{query_base64}` for the URL-encoded Base64 form.
+    pub fn new(query: &str, to_config: &PlatformConfig) -> Self {
+        let base64 = BASE64.encode(query);
+        let url = urlencoding::encode(query).into_owned();
+
+        let search_url = to_config.search_url.as_ref().map(|template| {
+            template
+                .replace("{query_base64}", &urlencoding::encode(&base64))
+                .replace("{query}", &url)
+        });
+
+        Self { base64, url, search_url }
+    }
+}
@@ -1,86 +1,92 @@
-use crate::config::PlatformConfig;
-use crate::error::{ConversionError, ConversionResult};
-use regex::Regex;
+use crate::config::{MatchMode, PlatformConfig};
+use crate::converter::{QueryLint, QueryLinter, QueryParser, ScannedToken};
+use crate::error::{ConversionError, ConversionResult, OperatorKind, Span};
+
+/// Field comparison found in a query, with the spans of its parts
+struct ClauseMatch {
+    pub field: String,
+    pub field_span: Span,
+    pub mode: MatchMode,
+    pub value: String,
+    pub value_span: Span,
+}
 
 /// Syntax validator for query statements
 pub struct SyntaxValidator;
 
 impl SyntaxValidator {
-    /// Normalize query by converting logical operators to uppercase
-    pub fn normalize_query(query: &str) -> String {
-        let mut normalized = query.to_string();
-
-        // Convert logical operators to uppercase using word boundaries
-        let replacements = [
-            (r"\band\b", "AND"),
-            (r"\bor\b", "OR"),
-            (r"\bnot\b", "NOT"),
-        ];
-
-        for (pattern, replacement) in replacements {
-            let re = Regex::new(pattern).unwrap();
-            normalized = re.replace_all(&normalized, replacement).to_string();
-        }
-
-        normalized
-    }
-
     /// Validate query syntax against platform configuration
+    ///
+    /// Returns the warnings of the [`QueryLinter`] for a valid query.
     pub fn validate_query_syntax(
         query: &str,
         from_config: &PlatformConfig,
         platform_name: &str,
-    ) -> ConversionResult<()> {
-        // First normalize the query
-        let normalized_query = Self::normalize_query(query);
+    ) -> ConversionResult<Vec<QueryLint>> {
+        // Split the query into tokens, so operators inside quoted values are not mistaken for operators
+        let tokens = QueryParser::scan(query);
 
         // Check operator consistency (all fields should use the same operator type)
-        Self::validate_operator_consistency(query, from_config, platform_name)?;
+        Self::validate_operator_consistency(&tokens, from_config, platform_name)?;
 
-        // Check operator support using normalized query
-        Self::validate_operators(&normalized_query, from_config, platform_name)?;
+        // Check operator support, with logical keywords in any case
+        Self::validate_operators(&tokens, from_config, platform_name)?;
 
-        // Check field support using original query (fields are case-sensitive)
-        Self::validate_fields(query, from_config, platform_name)?;
+        // Check field support (fields are case-sensitive)
+        Self::validate_fields(&tokens, from_config, platform_name)?;
 
-        Ok(())
+        // Check the structure of the query, such as balanced parentheses and quotes
+        let expr = QueryParser::parse(query, from_config)?;
+
+        // Check values and match modes against the field metadata
+        Self::validate_field_values(&tokens, from_config, platform_name)?;
+
+        // Warn about valid queries that probably do not match what was meant
+        Ok(QueryLinter::lint(&expr, from_config))
     }
 
     /// Validate operator consistency (all fields should use the same operator type)
     fn validate_operator_consistency(
-        query: &str,
+        tokens: &[(ScannedToken, Span)],
         from_config: &PlatformConfig,
         platform_name: &str,
     ) -> ConversionResult<()> {
-        let mut used_operators = std::collections::HashSet::new();
-
-        // Extract all field operators from the query
-        let re = Regex::new(r"\w+(?:\.\w+)*([=:])").unwrap();
-
-        for caps in re.captures_iter(query) {
-            if let Some(op_match) = caps.get(1) {
-                used_operators.insert(op_match.as_str());
+        // Map each used field operator to the span of its first occurrence
+        let mut used_operators = std::collections::BTreeMap::new();
+
+        for (token, _) in tokens {
+            if let ScannedToken::Clause { operator, operator_span, .. } = token {
+                // `!=` is checked as a negation, and `==` by the parser, as the exact match of `=`
+                let operator = match operator.as_str() {
+                    "!=" => continue,
+                    "==" => "=",
+                    operator => operator,
+                };
+                used_operators.entry(operator).or_insert(Span {
+                    start: operator_span.start,
+                    end: operator_span.start + operator.len(),
+                });
             }
         }
 
         // Check if multiple different operators are used
         if used_operators.len() > 1 {
-            let operators_list: Vec<&str> = used_operators.iter().cloned().collect();
-            return Err(ConversionError::SyntaxValidationFailed(
-                format!("Inconsistent field operators in query. Found: '{}'. {} platform expects consistent use of '{}'",
-                    operators_list.join(", "),
-                    platform_name.to_uppercase(),
-                    from_config.operators.equal)
-            ));
+            return Err(ConversionError::InconsistentOperators {
+                platform: platform_name.to_string(),
+                operators: used_operators.keys().map(|op| op.to_string()).collect(),
+                expected: from_config.operators.equal.clone(),
+            });
         }
 
         // Check if the used operator matches the platform's expected operator
-        if let Some(&used_op) = used_operators.iter().next() {
+        if let Some((&used_op, &span)) = used_operators.iter().next() {
             if used_op != from_config.operators.equal {
                 return Err(ConversionError::UnsupportedOperator {
                     platform: platform_name.to_string(),
+                    kind: OperatorKind::Field,
                     operator: format!("field{}", used_op),
                     suggestion: format!("field{}", from_config.operators.equal),
+                    span: Some(span),
                 });
             }
         }
@@ -88,103 +94,95 @@ impl SyntaxValidator {
         Ok(())
     }
 
-    /// Validate operators used in the query (simplified for normalized input)
+    /// Validate the logical and negation operators used in the query
     fn validate_operators(
-        query: &str,
+        tokens: &[(ScannedToken, Span)],
         from_config: &PlatformConfig,
         platform_name: &str,
     ) -> ConversionResult<()> {
-        // Check AND operator
-        if query.contains("AND") && from_config.operators.and != "AND" {
-            return Err(ConversionError::UnsupportedOperator {
-                platform: platform_name.to_string(),
-                operator: "AND".to_string(),
-                suggestion: from_config.operators.and.clone(),
-            });
-        }
-
-        // Check OR operator
-        if query.contains("OR") && from_config.operators.or != "OR" {
-            return Err(ConversionError::UnsupportedOperator {
-                platform: platform_name.to_string(),
-                operator: "OR".to_string(),
-                suggestion: from_config.operators.or.clone(),
-            });
-        }
+        let operators = &from_config.operators;
+        let checks = [
+            ("AND", operators.and == "AND", OperatorKind::Logical, &operators.and),
+            ("OR", operators.or == "OR", OperatorKind::Logical, &operators.or),
+            ("&&", operators.and == "&&", OperatorKind::Logical, &operators.and),
+            ("||", operators.or == "||", OperatorKind::Logical, &operators.or),
+            ("NOT", operators.not_equal.trim() == "NOT", OperatorKind::Negation, &operators.not_equal),
+            ("!=", operators.not_equal == "!=", OperatorKind::Negation, &operators.not_equal),
+        ];
 
-        // Check && operator
-        if query.contains("&&") && from_config.operators.and != "&&" {
-            return Err(ConversionError::UnsupportedOperator {
-                platform: platform_name.to_string(),
-                operator: "&&".to_string(),
-                suggestion: from_config.operators.and.clone(),
-            });
+        for (operator, supported, kind, suggestion) in checks {
+            if supported {
+                continue;
+            }
+            if let Some(span) = Self::find_operator(tokens, operator) {
+                return Err(ConversionError::UnsupportedOperator {
+                    platform: platform_name.to_string(),
+                    kind,
+                    operator: operator.to_string(),
+                    suggestion: suggestion.clone(),
+                    span: Some(span),
+                });
+            }
         }
 
-        // Check || operator
-        if query.contains("||") && from_config.operators.or != "||" {
-            return Err(ConversionError::UnsupportedOperator {
-                platform: platform_name.to_string(),
-                operator: "||".to_string(),
-                suggestion: from_config.operators.or.clone(),
-            });
-        }
+        Ok(())
+    }
 
-        // Check NOT operator
-        let not_re = Regex::new(r"\bNOT\s+\w+").unwrap();
-        if not_re.is_match(query) && from_config.operators.not_equal.trim() != "NOT" {
-            return Err(ConversionError::UnsupportedOperator {
-                platform: platform_name.to_string(),
-                operator: "NOT".to_string(),
-                suggestion: from_config.operators.not_equal.clone(),
-            });
-        }
+    /// Find the span of the first use of an operator, either between operands or in a clause
+    fn find_operator(tokens: &[(ScannedToken, Span)], operator: &str) -> Option<Span> {
+        tokens.iter().find_map(|(token, span)| match token {
+            ScannedToken::Operator(word) if word == operator => Some(*span),
+            ScannedToken::Clause { operator: used, operator_span, .. } if used == operator => Some(*operator_span),
+            _ => None,
+        })
+    }
 
-        // Check != operator
-        if query.contains("!=") && from_config.operators.not_equal != "!=" {
-            return Err(ConversionError::UnsupportedOperator {
-                platform: platform_name.to_string(),
-                operator: "!=".to_string(),
-                suggestion: from_config.operators.not_equal.clone(),
-            });
+    /// Validate fields used in the query: each must be a native name or alias of the platform
+    fn validate_fields(
+        tokens: &[(ScannedToken, Span)],
+        from_config: &PlatformConfig,
+        platform_name: &str,
+    ) -> ConversionResult<()> {
+        for (field_name, span) in Self::field_spans(tokens) {
+            if from_config.resolve_field(&field_name).is_none() {
+                return Err(ConversionError::UnsupportedField {
+                    platform: platform_name.to_string(),
+                    field: field_name,
+                    span: Some(span),
+                });
+            }
         }
 
         Ok(())
     }
 
-
-
-    /// Validate fields used in the query
-    fn validate_fields(
-        query: &str,
+    /// Validate each clause against the type and match modes of its field
+    fn validate_field_values(
+        tokens: &[(ScannedToken, Span)],
         from_config: &PlatformConfig,
         platform_name: &str,
     ) -> ConversionResult<()> {
-        let used_field_names = Self::extract_field_names_from_query(query);
-
-        for field_name in used_field_names {
-            // Check if this field name exists in the platform configuration
-            // First try exact match
-            if from_config.fields.contains_key(&field_name) {
+        for clause in Self::clauses(tokens) {
+            let Some((_, spec)) = from_config.resolve_field(&clause.field) else {
                 continue;
-            }
-
-            // Then try to match by base field name (for compound fields like response.title)
-            let base_field = if field_name.contains('.') {
-                field_name.split('.').last().unwrap_or(&field_name)
-            } else {
-                &field_name
             };
 
-            // Check if the base field exists in config
-            let is_supported = from_config.fields.iter().any(|(config_field, _)| {
-                config_field == base_field
-            });
+            if !spec.supports(clause.mode) {
+                return Err(ConversionError::UnsupportedMatchMode {
+                    platform: platform_name.to_string(),
+                    field: clause.field,
+                    mode: clause.mode.to_string(),
+                    span: Some(clause.field_span),
+                });
+            }
 
-            if !is_supported {
-                return Err(ConversionError::UnsupportedField {
+            if !spec.field_type.accepts(&clause.value) {
+                return Err(ConversionError::InvalidFieldValue {
                     platform: platform_name.to_string(),
-                    field: base_field.to_string(),
+                    field: clause.field,
+                    value: clause.value,
+                    expected: spec.field_type.to_string(),
+                    span: Some(clause.value_span),
                 });
             }
         }
@@ -192,25 +190,114 @@ impl SyntaxValidator {
         Ok(())
     }
 
-    /// Extract field names from query (without operators)
-    fn extract_field_names_from_query(query: &str) -> Vec<String> {
-        let mut field_names = Vec::new();
+    /// Collect the `field=value` clauses of the scanned tokens
+    fn clauses(tokens: &[(ScannedToken, Span)]) -> Vec<ClauseMatch> {
+        tokens
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (token, _))| {
+                let ScannedToken::Clause { field, field_span, operator, value, value_span, .. } = token else {
+                    return None;
+                };
+                let negated = operator == "!="
+                    || index
+                        .checked_sub(1)
+                        .is_some_and(|previous| matches!(&tokens[previous].0, ScannedToken::Operator(word) if word == "NOT"));
+                let mode = match (negated, operator.as_str()) {
+                    (true, _) => MatchMode::NotEqual,
+                    (false, "==") => MatchMode::Exact,
+                    (false, _) => MatchMode::Equal,
+                };
+
+                Some(ClauseMatch {
+                    field: field.clone(),
+                    field_span: *field_span,
+                    mode,
+                    value: value.clone(),
+                    value_span: *value_span,
+                })
+            })
+            .collect()
+    }
 
-        // Use regex to find all field names
-        let re = Regex::new(r"(\w+(?:\.\w+)*)(?:[:=]|!=)").unwrap();
+    /// Extract field names from query (without operators)
+    pub(crate) fn extract_field_names_from_query(query: &str) -> Vec<String> {
+        Self::field_spans(&QueryParser::scan(query))
+            .into_iter()
+            .map(|(field_name, _)| field_name)
+            .collect()
+    }
 
-        for caps in re.captures_iter(query) {
-            if let Some(field_match) = caps.get(1) {
-                let field_str = field_match.as_str();
+    /// Extract field names with the span of their first occurrence
+    fn field_spans(tokens: &[(ScannedToken, Span)]) -> Vec<(String, Span)> {
+        let mut fields: Vec<(String, Span)> = Vec::new();
 
-                if !field_names.contains(&field_str.to_string()) {
-                    field_names.push(field_str.to_string());
+        for (token, _) in tokens {
+            if let ScannedToken::Clause { field, field_span, .. } = token {
+                if !fields.iter().any(|(field_name, _)| field_name == field) {
+                    fields.push((field.clone(), *field_span));
                 }
             }
         }
 
-        field_names
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigManager, EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE};
+
+    fn validate(query: &str, platform: &str) -> ConversionResult<Vec<QueryLint>> {
+        let manager = ConfigManager::from_json_str(EMBEDDED_CONFIG, EMBEDDED_CONFIG_SOURCE).unwrap();
+        let config = manager.get_platform_config(platform).unwrap();
+        SyntaxValidator::validate_query_syntax(query, config, platform)
+    }
+
+    #[test]
+    fn operators_inside_quoted_values_are_ignored() {
+        assert!(validate(r#"title:"x && y" AND port:80"#, "quake").is_ok());
+        assert!(validate(r#"title="a AND b:c" && port="80""#, "fofa").is_ok());
+    }
+
+    #[test]
+    fn unsupported_operators_are_reported_at_their_span() {
+        let query = r#"title="a" AND port="80""#;
+        match validate(query, "fofa") {
+            Err(ConversionError::UnsupportedOperator { kind: OperatorKind::Logical, span: Some(span), .. }) => {
+                assert_eq!(&query[span.start..span.end], "AND");
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(matches!(
+            validate(r#"not title="a""#, "fofa"),
+            Err(ConversionError::UnsupportedOperator { kind: OperatorKind::Negation, .. })
+        ));
+        assert!(matches!(
+            validate(r#"title:"a""#, "fofa"),
+            Err(ConversionError::UnsupportedOperator { kind: OperatorKind::Field, .. })
+        ));
+        assert!(matches!(
+            validate(r#"title="a" || port:80"#, "fofa"),
+            Err(ConversionError::InconsistentOperators { .. })
+        ));
     }
 
+    #[test]
+    fn exact_matches_are_checked_as_exact() {
+        assert!(validate(r#"title=="a" && port="80""#, "fofa").is_ok());
+        let clauses = SyntaxValidator::clauses(&QueryParser::scan(r#"title=="a" && title!="b""#));
+        assert_eq!(clauses.iter().map(|clause| clause.mode).collect::<Vec<_>>(), [MatchMode::Exact, MatchMode::NotEqual]);
+    }
 
+    #[test]
+    fn negated_clauses_are_checked_as_not_equal() {
+        let clauses = SyntaxValidator::clauses(&QueryParser::scan(r#"NOT title:"a" AND port:[80 TO 443]"#));
+        let modes: Vec<_> = clauses.iter().map(|clause| (clause.field.as_str(), clause.mode, clause.value.as_str())).collect();
+        assert_eq!(
+            modes,
+            [("title", MatchMode::NotEqual, "a"), ("port", MatchMode::Equal, "[80 TO 443]")]
+        );
+    }
 }
@@ -0,0 +1,342 @@
+use crate::error::{ConversionError, OperatorKind};
+
+/// Stable identifier of an error class
+///
+/// Codes never change meaning once published; automation should branch on
+/// `code` (or `name`) rather than on message text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    /// Code such as `CX0102`
+    pub code: &'static str,
+    /// Kebab-case name such as `unsupported-field`
+    pub name: &'static str,
+    /// One-line summary
+    pub summary: &'static str,
+    /// Longer explanation of causes and fixes
+    pub explanation: &'static str,
+    /// Example command line that triggers the error
+    pub example: &'static str,
+}
+
+/// Platform is missing from the configuration
+pub const UNSUPPORTED_PLATFORM: ErrorCode = ErrorCode {
+    code: "CX0101",
+    name: "unsupported-platform",
+    summary: "The platform is not defined in the configuration",
+    explanation: "The source or target platform passed with -p/--platform or -t/--to has no entry in \
+the configuration file. Platform names are case-sensitive and match the top-level keys of the config.",
+    example: "convertix -q 'title=\"nginx\"' -p shodan",
+};
+
+/// Field has no entry for the platform
+pub const UNSUPPORTED_FIELD: ErrorCode = ErrorCode {
+    code: "CX0102",
+    name: "unsupported-field",
+    summary: "The query uses a field the source platform does not define",
+    explanation: "Every field in the query must be a native field name or alias of the source \
+platform in the configuration. Check the spelling or run 'convertix fields --describe -p <platform>' \
+to list the known fields and their aliases.",
+    example: "convertix -q 'nosuchfield=\"x\"' -p fofa",
+};
+
+/// `&&`/`||`/`AND`/`OR` of another platform
+pub const UNSUPPORTED_LOGICAL_OPERATOR: ErrorCode = ErrorCode {
+    code: "CX0103",
+    name: "unsupported-logical-operator",
+    summary: "The query combines clauses with a logical operator of another platform",
+    explanation: "Each platform uses its own spelling of AND and OR, for example '&&' and '||' on \
+FOFA but 'AND' and 'OR' on Quake. Use the operator suggested in the message.",
+    example: "convertix -q 'title=\"a\" AND port=\"80\"' -p fofa",
+};
+
+/// `NOT`/`!=` of another platform
+pub const UNSUPPORTED_NEGATION_OPERATOR: ErrorCode = ErrorCode {
+    code: "CX0104",
+    name: "unsupported-negation-operator",
+    summary: "The query negates a clause with an operator of another platform",
+    explanation: "Platforms negate clauses differently, for example field!=\"value\" on FOFA and \
+NOT field:\"value\" on Quake. Use the negation suggested in the message.",
+    example: "convertix -q 'NOT title=\"a\"' -p fofa",
+};
+
+/// `field:` where `field=` is expected or vice versa
+pub const UNSUPPORTED_FIELD_OPERATOR: ErrorCode = ErrorCode {
+    code: "CX0105",
+    name: "unsupported-field-operator",
+    summary: "Fields are matched with the operator of another platform",
+    explanation: "The operator between a field and its value is platform-specific, '=' on FOFA and \
+':' on Quake. Rewrite every clause with the suggested operator.",
+    example: "convertix -q 'title:\"a\"' -p fofa",
+};
+
+/// Mixed `=` and `:` within one query
+pub const INCONSISTENT_FIELD_OPERATORS: ErrorCode = ErrorCode {
+    code: "CX0106",
+    name: "inconsistent-field-operators",
+    summary: "The query mixes different field operators",
+    explanation: "All clauses of a query must use the same field operator. A mix such as \
+title=\"a\" && port:\"80\" usually means part of the query was copied from another platform.",
+    example: "convertix -q 'title=\"a\" && port:\"80\"' -p fofa",
+};
+
+/// Generic syntax error
+pub const SYNTAX_ERROR: ErrorCode = ErrorCode {
+    code: "CX0107",
+    name: "syntax-error",
+    summary: "The query is not valid syntax for the source platform",
+    explanation: "The query could not be understood, for example because of unbalanced parentheses \
+or quotes. The message describes the problem.",
+    example: "convertix -q 'title=\"a\" && (port=\"80\"' -p fofa",
+};
+
+/// `detect` found no platform
+pub const NO_MATCHING_PLATFORM: ErrorCode = ErrorCode {
+    code: "CX0108",
+    name: "no-matching-platform",
+    summary: "No configured platform accepts the query",
+    explanation: "'convertix detect' validated the query against every configured platform and all \
+of them rejected it. Validate against the intended platform to see the specific error.",
+    example: "convertix detect -q 'nosuchfield=\"x\"'",
+};
+
+/// Value of the wrong type for the field
+pub const INVALID_FIELD_VALUE: ErrorCode = ErrorCode {
+    code: "CX0109",
+    name: "invalid-field-value",
+    summary: "A field is compared with a value of the wrong type",
+    explanation: "The configuration declares a type for the field, such as number, ip, date or \
+boolean, and the value in the query is not of that type. Run 'convertix fields --describe' to see \
+field types and example values.",
+    example: "convertix -q 'port=\"http\"' -p fofa",
+};
+
+/// Field cannot be negated or compared the requested way
+pub const UNSUPPORTED_MATCH_MODE: ErrorCode = ErrorCode {
+    code: "CX0110",
+    name: "unsupported-match-mode",
+    summary: "A field is matched in a way the platform does not support",
+    explanation: "The configuration restricts how the field can be matched, for example a field \
+that can be searched for but not excluded with a negation. Run 'convertix fields --describe' to \
+see the match modes of each field. It is also reported when converting an exact match ('==') to a \
+platform without one while the field's policy is 'error'.",
+    example: "convertix -q 'title==\"a\"' -p fofa -t quake --on-missing error",
+};
+
+/// Field without equivalent on the target platform
+pub const NO_EQUIVALENT_FIELD: ErrorCode = ErrorCode {
+    code: "CX0111",
+    name: "no-equivalent-field",
+    summary: "A field of the query has no equivalent on the target platform",
+    explanation: "The field only exists on the source platform and its 'on_missing' policy, or the \
+--on-missing option, is 'error', or every clause of the query was dropped by the 'drop' policy. Add \
+an 'approximations' entry for the target platform to the field, use --on-missing keep or drop, or \
+leave the target platform out.",
+    example: "convertix -q 'title:\"a\" AND is_domain:true' -p quake -t fofa --on-missing error",
+};
+
+/// Configuration could not be loaded
+pub const CONFIGURATION_ERROR: ErrorCode = ErrorCode {
+    code: "CX0201",
+    name: "configuration-error",
+    summary: "The configuration file could not be loaded",
+    explanation: "The configuration file is missing, unreadable or not in the expected format. \
+Pass the right file with -c/--config and check it with 'convertix config check'.",
+    example: "convertix -c missing.json platforms",
+};
+
+/// Input or output file access failed
+pub const IO_ERROR: ErrorCode = ErrorCode {
+    code: "CX0301",
+    name: "io-error",
+    summary: "Reading input or writing output failed",
+    explanation: "A query file, batch file, stdin or the output file could not be accessed. The \
+message names the file and the operating system error.",
+    example: "convertix -q @missing.txt -p fofa",
+};
+
+/// Serialization or deserialization failed
+pub const SERIALIZATION_ERROR: ErrorCode = ErrorCode {
+    code: "CX0401",
+    name: "serialization-error",
+    summary: "Data could not be serialized or parsed",
+    explanation: "Output could not be serialized, or a structured input such as a JSON or YAML \
+batch file does not have the expected shape.",
+    example: "convertix batch -i broken.json -p fofa",
+};
+
+/// Some batch entries failed
+pub const BATCH_FAILED: ErrorCode = ErrorCode {
+    code: "CX0501",
+    name: "batch-failed",
+    summary: "Some queries of a batch failed to convert",
+    explanation: "A batch conversion completed but at least one query was rejected. The per-query \
+results carry the error of each failed query.",
+    example: "convertix batch -i playbook.txt -p fofa",
+};
+
+/// No query on the command line or stdin
+pub const MISSING_QUERY: ErrorCode = ErrorCode {
+    code: "CX0601",
+    name: "missing-query",
+    summary: "No query was given",
+    explanation: "Pass the query with -q, read it from a file with -q @filename, or pipe it on \
+stdin with -q - or by omitting -q.",
+    example: "convertix -p fofa < /dev/null",
+};
+
+/// Unknown code passed to `explain-error`
+pub const UNKNOWN_ERROR_CODE: ErrorCode = ErrorCode {
+    code: "CX0602",
+    name: "unknown-error-code",
+    summary: "The error code is not known",
+    explanation: "'convertix explain-error' was given a code or name that does not exist. Run it \
+without arguments to list all error codes.",
+    example: "convertix explain-error CX9999",
+};
+
+/// Batch query without a source platform
+pub const MISSING_PLATFORM: ErrorCode = ErrorCode {
+    code: "CX0603",
+    name: "missing-platform",
+    summary: "A batch query names no source platform",
+    explanation: "Entries of a JSON or YAML batch file without a 'platform' key are read as queries \
+of the platform given with -p. Give -p, or a 'platform' key on each entry.",
+    example: "convertix batch -i queries.json -t fofa",
+};
+
+/// `fmt --check` failed
+pub const NOT_FORMATTED: ErrorCode = ErrorCode {
+    code: "CX0701",
+    name: "not-formatted",
+    summary: "A query or query file is not formatted",
+    explanation: "'convertix fmt --check' found a query that changes when formatted. Run 'convertix \
+fmt' to print the formatted query, or 'convertix fmt -w' to rewrite query files in place.",
+    example: "convertix fmt --check -q '((title=\"a\"))' -p fofa",
+};
+
+/// `equiv` found a difference
+pub const NOT_EQUIVALENT: ErrorCode = ErrorCode {
+    code: "CX0702",
+    name: "not-equivalent",
+    summary: "Two queries do not match the same records",
+    explanation: "'convertix equiv' found a record that one query matches and the other does not. \
+The output lists the conditions that record matches; it matches none of the other conditions of \
+the two queries.",
+    example: "convertix equiv -a 'port=\"80\" || port=\"443\"' --pa fofa -b 'port:80' --pb quake",
+};
+
+/// `equiv` cannot enumerate the conditions
+pub const TOO_MANY_CONDITIONS: ErrorCode = ErrorCode {
+    code: "CX0703",
+    name: "too-many-conditions",
+    summary: "Two queries have too many conditions to compare",
+    explanation: "'convertix equiv' evaluates the queries for every combination of their distinct \
+clauses and search terms, so it accepts at most 20 of them. Compare smaller parts of the queries \
+separately.",
+    example: "",
+};
+
+/// Unexpected internal failure
+pub const INTERNAL_ERROR: ErrorCode = ErrorCode {
+    code: "CX0901",
+    name: "internal-error",
+    summary: "An unexpected internal error occurred",
+    explanation: "A query passed validation but could not be converted. Please report it together \
+with the query and configuration.",
+    example: "",
+};
+
+/// All error codes, ordered by code
+pub const ERROR_CODES: &[ErrorCode] = &[
+    UNSUPPORTED_PLATFORM,
+    UNSUPPORTED_FIELD,
+    UNSUPPORTED_LOGICAL_OPERATOR,
+    UNSUPPORTED_NEGATION_OPERATOR,
+    UNSUPPORTED_FIELD_OPERATOR,
+    INCONSISTENT_FIELD_OPERATORS,
+    SYNTAX_ERROR,
+    NO_MATCHING_PLATFORM,
+    INVALID_FIELD_VALUE,
+    UNSUPPORTED_MATCH_MODE,
+    NO_EQUIVALENT_FIELD,
+    CONFIGURATION_ERROR,
+    IO_ERROR,
+    SERIALIZATION_ERROR,
+    BATCH_FAILED,
+    MISSING_QUERY,
+    UNKNOWN_ERROR_CODE,
+    MISSING_PLATFORM,
+    NOT_FORMATTED,
+    NOT_EQUIVALENT,
+    TOO_MANY_CONDITIONS,
+    INTERNAL_ERROR,
+];
+
+impl ErrorCode {
+    /// Look up an error code by code (case-insensitive) or name
+    pub fn lookup(code: &str) -> Option<&'static ErrorCode> {
+        ERROR_CODES
+            .iter()
+            .find(|error_code| error_code.code.eq_ignore_ascii_case(code) || error_code.name == code)
+    }
+}
+
+impl ConversionError {
+    /// Get the stable error code of this error
+    pub fn code(&self) -> &'static ErrorCode {
+        match self {
+            ConversionError::UnsupportedPlatform(_) => &UNSUPPORTED_PLATFORM,
+            ConversionError::SyntaxValidationFailed { .. } => &SYNTAX_ERROR,
+            ConversionError::UnsupportedField { .. } => &UNSUPPORTED_FIELD,
+            ConversionError::UnsupportedOperator { kind, .. } => match kind {
+                OperatorKind::Logical => &UNSUPPORTED_LOGICAL_OPERATOR,
+                OperatorKind::Negation => &UNSUPPORTED_NEGATION_OPERATOR,
+                OperatorKind::Field => &UNSUPPORTED_FIELD_OPERATOR,
+            },
+            ConversionError::InvalidFieldValue { .. } => &INVALID_FIELD_VALUE,
+            ConversionError::UnsupportedMatchMode { .. } => &UNSUPPORTED_MATCH_MODE,
+            ConversionError::NoEquivalentField { .. } => &NO_EQUIVALENT_FIELD,
+            ConversionError::InconsistentOperators { .. } => &INCONSISTENT_FIELD_OPERATORS,
+            ConversionError::NoMatchingPlatform => &NO_MATCHING_PLATFORM,
+            ConversionError::ConfigurationError(_) => &CONFIGURATION_ERROR,
+            ConversionError::MissingQuery(_) => &MISSING_QUERY,
+            ConversionError::UnknownErrorCode(_) => &UNKNOWN_ERROR_CODE,
+            ConversionError::MissingPlatform => &MISSING_PLATFORM,
+            ConversionError::IoError(_) => &IO_ERROR,
+            ConversionError::SerializationError(_) => &SERIALIZATION_ERROR,
+            ConversionError::BatchFailed { .. } => &BATCH_FAILED,
+            ConversionError::NotFormatted(_) => &NOT_FORMATTED,
+            ConversionError::NotEquivalent => &NOT_EQUIVALENT,
+            ConversionError::TooManyConditions { .. } => &TOO_MANY_CONDITIONS,
+            ConversionError::InternalError(_) => &INTERNAL_ERROR,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_unique() {
+        for (i, error_code) in ERROR_CODES.iter().enumerate() {
+            assert!(ERROR_CODES[i + 1..].iter().all(|other| other.code != error_code.code && other.name != error_code.name));
+        }
+    }
+
+    #[test]
+    fn operator_codes_follow_the_kind() {
+        let error = |kind, operator: &str| ConversionError::UnsupportedOperator {
+            platform: "fofa".to_string(),
+            kind,
+            operator: operator.to_string(),
+            suggestion: String::new(),
+            span: None,
+        };
+
+        // The operator text does not decide the code
+        assert_eq!(error(OperatorKind::Logical, "NOT").code().code, "CX0103");
+        assert_eq!(error(OperatorKind::Negation, "AND").code().code, "CX0104");
+        assert_eq!(error(OperatorKind::Field, ":").code().code, "CX0105");
+    }
+}
@@ -1,3 +1,7 @@
+pub mod codes;
+pub mod report;
 pub mod types;
 
+pub use codes::{ErrorCode, ERROR_CODES};
+pub use report::*;
 pub use types::*;
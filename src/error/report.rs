@@ -0,0 +1,124 @@
+use serde::Serialize;
+use crate::error::{ConversionError, Span};
+
+/// Machine-readable description of a conversion error
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    /// Stable error code, e.g. `CX0102`
+    pub code: &'static str,
+    /// Error class, e.g. `unsupported-field`
+    pub kind: &'static str,
+    /// Human-readable message, identical to the text output
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    /// Byte range of the offending part of the query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl From<&ConversionError> for ErrorReport {
+    fn from(error: &ConversionError) -> Self {
+        let mut report = ErrorReport {
+            code: error.code().code,
+            kind: error.code().name,
+            message: error.to_string(),
+            platform: None,
+            field: None,
+            operator: None,
+            span: None,
+            suggestion: None,
+        };
+
+        match error {
+            ConversionError::UnsupportedPlatform(platform) => {
+                report.platform = Some(platform.clone());
+                report.suggestion = Some("Run 'convertix platforms' to list supported platforms".to_string());
+            }
+            ConversionError::UnsupportedField { platform, field, span } => {
+                report.platform = Some(platform.clone());
+                report.field = Some(field.clone());
+                report.span = *span;
+                report.suggestion = Some(format!("Run 'convertix fields -p {}' to list supported fields", platform));
+            }
+            ConversionError::UnsupportedOperator { platform, operator, suggestion, span, .. } => {
+                report.platform = Some(platform.clone());
+                report.operator = Some(operator.clone());
+                report.span = *span;
+                report.suggestion = Some(suggestion.clone());
+            }
+            ConversionError::InvalidFieldValue { platform, field, expected, span, .. } => {
+                report.platform = Some(platform.clone());
+                report.field = Some(field.clone());
+                report.span = *span;
+                report.suggestion = Some(format!("Use a {} value", expected));
+            }
+            ConversionError::UnsupportedMatchMode { platform, field, span, .. } => {
+                report.platform = Some(platform.clone());
+                report.field = Some(field.clone());
+                report.span = *span;
+                report.suggestion = Some(format!("Run 'convertix fields --describe -p {}' to list match modes", platform));
+            }
+            ConversionError::SyntaxValidationFailed { span, .. } => {
+                report.span = *span;
+            }
+            ConversionError::NoEquivalentField { platform, field } => {
+                report.platform = Some(platform.clone());
+                report.field = Some(field.clone());
+                report.suggestion = Some("Use --on-missing keep or drop to convert anyway".to_string());
+            }
+            _ => {}
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::OperatorKind;
+    use serde_json::json;
+
+    #[test]
+    fn reports_carry_the_details_of_the_error() {
+        let error = ConversionError::UnsupportedOperator {
+            platform: "FOFA".to_string(),
+            kind: OperatorKind::Logical,
+            operator: "AND".to_string(),
+            suggestion: "&&".to_string(),
+            span: Some(Span { start: 10, end: 13 }),
+        };
+        assert_eq!(
+            serde_json::to_value(ErrorReport::from(&error)).unwrap(),
+            json!({
+                "code": "CX0103",
+                "kind": error.code().name,
+                "message": error.to_string(),
+                "platform": "FOFA",
+                "operator": "AND",
+                "span": { "start": 10, "end": 13 },
+                "suggestion": "&&",
+            })
+        );
+    }
+
+    #[test]
+    fn missing_details_are_left_out() {
+        let error = ConversionError::IoError("Failed to read 'q.txt'".to_string());
+        assert_eq!(
+            serde_json::to_value(ErrorReport::from(&error)).unwrap(),
+            json!({
+                "code": error.code().code,
+                "kind": error.code().name,
+                "message": error.to_string(),
+            })
+        );
+    }
+}
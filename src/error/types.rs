@@ -1,18 +1,67 @@
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
+/// Byte range of the offending part of a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Kind of an operator a platform does not support
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperatorKind {
+    /// `AND`/`OR` or their symbols
+    Logical,
+    /// `NOT` or `!=`
+    Negation,
+    /// Operator between a field and its value, such as `=` or `:`
+    Field,
+}
+
 /// Errors that can occur during query conversion
 #[derive(Debug, Clone)]
 pub enum ConversionError {
     /// Platform is not supported
     UnsupportedPlatform(String),
-    /// Syntax validation failed
-    SyntaxValidationFailed(String),
+    /// Query could not be parsed
+    SyntaxValidationFailed { message: String, span: Option<Span> },
     /// Field is not supported by the platform
-    UnsupportedField { platform: String, field: String },
+    UnsupportedField { platform: String, field: String, span: Option<Span> },
     /// Operator is not supported by the platform
-    UnsupportedOperator { platform: String, operator: String, suggestion: String },
+    UnsupportedOperator { platform: String, kind: OperatorKind, operator: String, suggestion: String, span: Option<Span> },
+    /// Value does not match the type of the field
+    InvalidFieldValue { platform: String, field: String, value: String, expected: String, span: Option<Span> },
+    /// Field cannot be matched the way the query matches it
+    UnsupportedMatchMode { platform: String, field: String, mode: String, span: Option<Span> },
+    /// Field has no equivalent on the target platform and its policy is `error`
+    NoEquivalentField { platform: String, field: String },
+    /// Fields of a query mix different field operators
+    InconsistentOperators { platform: String, operators: Vec<String>, expected: String },
+    /// No configured platform accepts the query
+    NoMatchingPlatform,
     /// Configuration loading failed
     ConfigurationError(String),
+    /// No query was given on the command line or stdin
+    MissingQuery(String),
+    /// Error code passed to `explain-error` does not exist
+    UnknownErrorCode(String),
+    /// A batch query names no source platform and no default was given with `-p`
+    MissingPlatform,
+    /// Reading input or writing output failed
+    IoError(String),
+    /// Serializing or deserializing data failed
+    SerializationError(String),
+    /// Some queries of a batch failed to convert
+    BatchFailed { failed: usize, total: usize },
+    /// `fmt --check` found queries that are not formatted
+    NotFormatted(Vec<String>),
+    /// `equiv` found a record matched by one of the queries only
+    ///
+    /// Only sets the exit code: `equiv` has already printed the record.
+    NotEquivalent,
+    /// Too many distinct conditions for `equiv` to compare
+    TooManyConditions { count: usize, limit: usize },
     /// Internal conversion error
     InternalError(String),
 }
@@ -23,19 +72,60 @@ impl fmt::Display for ConversionError {
             ConversionError::UnsupportedPlatform(platform) => {
                 write!(f, "Unsupported platform: {}", platform)
             }
-            ConversionError::SyntaxValidationFailed(msg) => {
-                write!(f, "{}", msg)
+            ConversionError::SyntaxValidationFailed { message, .. } => {
+                write!(f, "{}", message)
             }
-            ConversionError::UnsupportedField { platform, field } => {
+            ConversionError::UnsupportedField { platform, field, .. } => {
                 write!(f, "{} platform does not support field '{}'", platform.to_uppercase(), field)
             }
-            ConversionError::UnsupportedOperator { platform, operator, suggestion } => {
+            ConversionError::UnsupportedOperator { platform, operator, suggestion, .. } => {
                 write!(f, "{} platform does not support '{}' operator, please use '{}' instead",
                     platform.to_uppercase(), operator, suggestion)
             }
+            ConversionError::InvalidFieldValue { platform, field, value, expected, .. } => {
+                write!(f, "{} field '{}' expects a {} value, got '{}'", platform.to_uppercase(), field, expected, value)
+            }
+            ConversionError::UnsupportedMatchMode { platform, field, mode, .. } => {
+                write!(f, "{} field '{}' does not support '{}' matching", platform.to_uppercase(), field, mode)
+            }
+            ConversionError::NoEquivalentField { platform, field } => {
+                write!(f, "Field '{}' has no equivalent on {}", field, platform.to_uppercase())
+            }
+            ConversionError::InconsistentOperators { platform, operators, expected } => {
+                write!(f, "Inconsistent field operators in query. Found: '{}'. {} platform expects consistent use of '{}'",
+                    operators.join(", "), platform.to_uppercase(), expected)
+            }
+            ConversionError::NoMatchingPlatform => {
+                write!(f, "Query is not valid for any configured platform")
+            }
             ConversionError::ConfigurationError(msg) => {
                 write!(f, "Configuration error: {}", msg)
             }
+            ConversionError::MissingQuery(msg) => {
+                write!(f, "{}", msg)
+            }
+            ConversionError::MissingPlatform => {
+                write!(f, "No source platform given for query, use -p <PLATFORM> or a 'platform' key")
+            }
+            ConversionError::UnknownErrorCode(code) => {
+                write!(f, "Unknown error code '{}', run 'convertix explain-error' to list all codes", code)
+            }
+            ConversionError::IoError(msg) => {
+                write!(f, "I/O error: {}", msg)
+            }
+            ConversionError::SerializationError(msg) => {
+                write!(f, "Serialization error: {}", msg)
+            }
+            ConversionError::BatchFailed { failed, total } => {
+                write!(f, "Batch conversion failed for {} of {} queries", failed, total)
+            }
+            ConversionError::NotFormatted(targets) => {
+                write!(f, "Not formatted: {}; run 'convertix fmt' without --check to format", targets.join(", "))
+            }
+            ConversionError::NotEquivalent => write!(f, "The queries are not equivalent"),
+            ConversionError::TooManyConditions { count, limit } => {
+                write!(f, "The queries have {} distinct conditions, more than the {} that can be compared", count, limit)
+            }
             ConversionError::InternalError(msg) => {
                 write!(f, "Internal error: {}", msg)
             }
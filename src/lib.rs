@@ -1,11 +1,13 @@
+mod batch;
 mod cli;
 mod config;
 mod converter;
 mod error;
 mod output;
 
-pub use cli::Args;
-pub use config::ConfigManager;
-pub use converter::QueryConverter;
-pub use error::ConversionError;
+pub use batch::{BatchEntry, BatchInput, BatchProcessor, BatchRecord};
+pub use cli::{run, Args};
+pub use config::{ConfigLoader, ConfigManager};
+pub use converter::{ConversionReport, EncodedQuery, FieldMapping, PlatformConversion, QueryConverter};
+pub use error::{ConversionError, ErrorCode, ErrorReport, Span, ERROR_CODES};
 pub use output::OutputFormatter;
@@ -1,61 +1,94 @@
 use clap::Parser;
 use std::process;
-use convertix::{Args, ConfigManager, OutputFormatter, QueryConverter};
+use convertix::{Args, ConversionError, ErrorReport};
 
 fn main() {
     let args = Args::parse();
+    let json_errors = args.wants_json_errors();
 
-    // Parse query - support reading from file if starts with @
-    let query = args.parse_query_input();
-
-    // Load configuration
-    let config_manager = match ConfigManager::from_file(&args.config) {
-        Ok(config) => config,
-        Err(e) => {
-            eprintln!("ERROR: Failed to load configuration file: {}", e);
-            eprintln!("Please ensure configuration file '{}' exists and is properly formatted", args.config.display());
-            process::exit(1);
+    if let Err(e) = convertix::run(args) {
+        // A difference found by `equiv` is its result, not an error to report
+        if !matches!(e, ConversionError::NotEquivalent) {
+            report_error(&e, json_errors);
         }
-    };
+        process::exit(exit_code(&e));
+    }
+}
 
-    // Create converter
-    let converter = QueryConverter::new(config_manager);
-    let supported_platforms = converter.get_supported_platforms();
+/// Print an error to stderr, as a JSON object when JSON output was requested
+fn report_error(error: &ConversionError, json_errors: bool) {
+    if json_errors {
+        if let Ok(json) = serde_json::to_string(&ErrorReport::from(error)) {
+            eprintln!("{}", json);
+            return;
+        }
+    }
 
-    // Validate platform support
-    if !supported_platforms.contains(&args.platform) {
-        eprintln!("ERROR: Unsupported platform: {}", args.platform);
-        eprintln!("Supported platforms: {}", supported_platforms.join(", "));
-        process::exit(1);
+    let code = error.code().code;
+    eprintln!("ERROR[{}]: {}", code, error);
+    if let ConversionError::UnsupportedPlatform(_) = error {
+        eprintln!("Run 'convertix platforms' to list supported platforms");
     }
+    eprintln!("For more information about this error, run 'convertix explain-error {}'", code);
+}
 
-    // Validate query syntax for source platform
-    if let Err(e) = converter.validate_query_syntax(&query, &args.platform) {
-        eprintln!("ERROR: {}", e);
-        process::exit(1);
+/// Map an error to the process exit code documented in the `--help` output
+///
+/// - 1: internal error
+/// - 2: usage error (no query, source platform or known error code given; clap also exits with 2 on invalid arguments)
+/// - 3: configuration error
+/// - 4: query rejected (unsupported platform, field, operator, invalid syntax, untranslatable field or too many conditions to compare)
+/// - 5: I/O error
+/// - 6: serialization error
+/// - 7: batch conversion finished with failed queries
+/// - 8: a check failed: `fmt --check` found unformatted queries or `equiv` found a difference
+fn exit_code(error: &ConversionError) -> i32 {
+    match error {
+        ConversionError::InternalError(_) => 1,
+        ConversionError::MissingQuery(_) | ConversionError::UnknownErrorCode(_) | ConversionError::MissingPlatform => 2,
+        ConversionError::ConfigurationError(_) => 3,
+        ConversionError::UnsupportedPlatform(_)
+        | ConversionError::SyntaxValidationFailed { .. }
+        | ConversionError::UnsupportedField { .. }
+        | ConversionError::UnsupportedOperator { .. }
+        | ConversionError::InvalidFieldValue { .. }
+        | ConversionError::UnsupportedMatchMode { .. }
+        | ConversionError::NoEquivalentField { .. }
+        | ConversionError::InconsistentOperators { .. }
+        | ConversionError::NoMatchingPlatform
+        | ConversionError::TooManyConditions { .. } => 4,
+        ConversionError::IoError(_) => 5,
+        ConversionError::SerializationError(_) => 6,
+        ConversionError::BatchFailed { .. } => 7,
+        ConversionError::NotFormatted(_) | ConversionError::NotEquivalent => 8,
     }
+}
 
-    // Perform conversions
-    let mut conversions = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for target_platform in &supported_platforms {
-        if target_platform != &args.platform {
-            // Syntax validation completed at program start, conversion should always succeed here
-            match converter.convert(&query, &args.platform, target_platform) {
-                Ok(converted_query) => {
-                    conversions.push((target_platform.clone(), converted_query));
-                }
-                Err(e) => {
-                    // This should theoretically not happen since syntax has been validated
-                    eprintln!("INTERNAL ERROR: Failed to convert to {}: {}", target_platform, e);
-                    process::exit(1);
-                }
-            }
+    #[test]
+    fn errors_map_to_the_documented_exit_codes() {
+        let cases = [
+            (ConversionError::InternalError("x".to_string()), 1),
+            (ConversionError::MissingQuery("x".to_string()), 2),
+            (ConversionError::UnknownErrorCode("CX9999".to_string()), 2),
+            (ConversionError::MissingPlatform, 2),
+            (ConversionError::ConfigurationError("x".to_string()), 3),
+            (ConversionError::UnsupportedPlatform("x".to_string()), 4),
+            (ConversionError::SyntaxValidationFailed { message: "x".to_string(), span: None }, 4),
+            (ConversionError::NoEquivalentField { platform: "fofa".to_string(), field: "x".to_string() }, 4),
+            (ConversionError::NoMatchingPlatform, 4),
+            (ConversionError::TooManyConditions { count: 21, limit: 20 }, 4),
+            (ConversionError::IoError("x".to_string()), 5),
+            (ConversionError::SerializationError("x".to_string()), 6),
+            (ConversionError::BatchFailed { failed: 1, total: 2 }, 7),
+            (ConversionError::NotFormatted(vec!["query".to_string()]), 8),
+            (ConversionError::NotEquivalent, 8),
+        ];
+        for (error, code) in cases {
+            assert_eq!(exit_code(&error), code, "{:?}", error);
         }
     }
-
-    // Generate and output results
-    let output_content = OutputFormatter::format_output(&args.format, &args.platform, &query, &conversions);
-    OutputFormatter::write_output(output_content, args.output);
 }
-
@@ -1,89 +1,649 @@
-use crate::cli::OutputFormat;
-use serde_json::json;
+use crate::batch::BatchRecord;
+use crate::cli::{BatchFormat, ConfigOutputFormat, MatrixFormat, OutputFormat};
+use crate::config::{ConfigIssue, ConfigManager, FieldCatalog, FieldMatrix, IssueSeverity, ResolvedConfig};
+use crate::converter::ConversionReport;
+use crate::error::{ConversionError, ConversionResult};
+use serde_json::Value;
 use std::fs;
+use std::io::{self, Write};
 use std::path::PathBuf;
-use std::process;
 
 /// Output formatter for conversion results
 pub struct OutputFormatter;
 
 impl OutputFormatter {
     /// Generate raw text output
-    pub fn generate_raw_output(
-        platform: &str,
-        query: &str,
-        conversions: &[(String, String)],
-    ) -> String {
+    pub fn generate_raw_output(report: &ConversionReport) -> String {
         let mut output = String::new();
-        output.push_str(&format!("Source platform: {}\n", platform));
-        output.push_str(&format!("Original query: {}\n", query));
-        output.push_str("\n");
+        output.push_str(&format!("Source platform: {}\n", report.source_platform));
+        output.push_str(&format!("Original query: {}\n", report.original_query));
+        for warning in &report.warnings {
+            output.push_str(&format!("Warning: {}\n", warning));
+        }
+        output.push('\n');
 
-        for (platform, converted_query) in conversions {
+        for conversion in &report.conversions {
             output.push_str(&format!(
-                "[-] {}:\n{}\n\n",
-                platform.to_uppercase(),
-                converted_query
+                "[-] {}:\n{}\n",
+                conversion.platform.to_uppercase(),
+                conversion.query
             ));
+            for warning in &conversion.warnings {
+                output.push_str(&format!("Warning: {}\n", warning));
+            }
+            output.push('\n');
         }
 
         output
     }
 
+    /// Generate plain output: the bare query for a single target, `platform\tquery` lines otherwise
+    pub fn generate_plain_output(report: &ConversionReport) -> String {
+        match report.conversions.as_slice() {
+            [conversion] => format!("{}\n", conversion.query),
+            conversions => conversions
+                .iter()
+                .map(|conversion| format!("{}\t{}\n", conversion.platform, conversion.query))
+                .collect(),
+        }
+    }
+
     /// Generate JSON output
-    pub fn generate_json_output(
-        source_platform: &str,
-        query: &str,
-        conversions: &[(String, String)],
-    ) -> String {
-        let mut converted_queries = serde_json::Map::new();
+    pub fn generate_json_output(report: &ConversionReport) -> ConversionResult<String> {
+        serde_json::to_string_pretty(report)
+            .map_err(|e| ConversionError::SerializationError(format!("Failed to serialize JSON output: {}", e)))
+    }
 
-        for (platform, query) in conversions {
-            converted_queries.insert(platform.clone(), json!(query));
+    /// Generate YAML output
+    pub fn generate_yaml_output(report: &ConversionReport) -> ConversionResult<String> {
+        serde_yaml::to_string(report)
+            .map_err(|e| ConversionError::SerializationError(format!("Failed to serialize YAML output: {}", e)))
+    }
+
+    /// Generate CSV output with one row per target platform
+    pub fn generate_csv_output(report: &ConversionReport) -> String {
+        let mut output =
+            String::from("source_platform,original_query,target_platform,converted_query,warnings,search_url\n");
+
+        for conversion in &report.conversions {
+            // Warnings about the original query are repeated on every row
+            let warnings: Vec<String> = report.warnings.iter().chain(&conversion.warnings).cloned().collect();
+            let warnings = warnings.join("; ");
+            let search_url = conversion.encoded.search_url.as_deref().unwrap_or("");
+            let cells = [
+                report.source_platform.as_str(),
+                report.original_query.as_str(),
+                conversion.platform.as_str(),
+                conversion.query.as_str(),
+                warnings.as_str(),
+                search_url,
+            ];
+            let cells: Vec<String> = cells.iter().map(|cell| csv_escape(cell)).collect();
+            output.push_str(&cells.join(","));
+            output.push('\n');
         }
 
-        let result = json!({
-            "source_platform": source_platform,
-            "original_query": query,
-            "converted_queries": converted_queries,
-        });
+        output
+    }
 
-        serde_json::to_string_pretty(&result).unwrap_or_else(|e| {
-            eprintln!("ERROR: Failed to serialize JSON output: {}", e);
-            process::exit(1);
-        })
+    /// Generate a Markdown table with one row per target platform
+    pub fn generate_markdown_output(report: &ConversionReport) -> String {
+        let has_warnings = report.conversions.iter().any(|conversion| !conversion.warnings.is_empty());
+        let has_search_urls = report.conversions.iter().any(|conversion| conversion.encoded.search_url.is_some());
+
+        let mut output = String::new();
+        output.push_str(&format!("**Source platform:** {}\n\n", report.source_platform));
+        output.push_str(&format!("**Original query:** {}\n\n", markdown_code(&report.original_query)));
+        for warning in &report.warnings {
+            output.push_str(&format!("**Warning:** {}\n\n", markdown_escape(warning)));
+        }
+
+        let mut header = vec!["Platform", "Query"];
+        if has_warnings {
+            header.push("Warnings");
+        }
+        if has_search_urls {
+            header.push("Search");
+        }
+        output.push_str(&format!("| {} |\n", header.join(" | ")));
+        output.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+
+        for conversion in &report.conversions {
+            let mut cells = vec![
+                conversion.platform.clone(),
+                markdown_code(&markdown_escape(&conversion.query)),
+            ];
+            if has_warnings {
+                cells.push(markdown_escape(&conversion.warnings.join("<br>")));
+            }
+            if has_search_urls {
+                cells.push(
+                    conversion
+                        .encoded
+                        .search_url
+                        .as_ref()
+                        .map(|url| format!("[open]({})", url))
+                        .unwrap_or_default(),
+                );
+            }
+            output.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+
+        output
     }
 
     /// Write output to file or stdout
-    pub fn write_output(content: String, output_path: Option<PathBuf>) {
+    ///
+    /// The note naming a written file goes to stderr, so stdout stays free for the output.
+    pub fn write_output(content: String, output_path: Option<PathBuf>) -> ConversionResult<()> {
         match output_path {
             Some(path) => {
-                if let Err(e) = fs::write(&path, content) {
-                    eprintln!(
-                        "ERROR: Failed to write to output file '{}': {}",
-                        path.display(),
-                        e
-                    );
-                    process::exit(1);
-                }
-                println!("Output written to: {}", path.display());
+                fs::write(&path, content).map_err(|e| {
+                    ConversionError::IoError(format!("Failed to write to output file '{}': {}", path.display(), e))
+                })?;
+                eprintln!("Output written to: {}", path.display());
             }
             None => {
-                print!("{}", content);
+                let mut stdout = io::stdout();
+                stdout
+                    .write_all(content.as_bytes())
+                    .and_then(|_| stdout.flush())
+                    .map_err(|e| ConversionError::IoError(format!("Failed to write output: {}", e)))?;
             }
         }
+
+        Ok(())
     }
 
     /// Format output based on the specified format
-    pub fn format_output(
-        format: &OutputFormat,
-        source_platform: &str,
-        query: &str,
-        conversions: &[(String, String)],
-    ) -> String {
+    pub fn format_output(format: &OutputFormat, report: &ConversionReport) -> ConversionResult<String> {
+        match format {
+            OutputFormat::Raw => Ok(Self::generate_raw_output(report)),
+            OutputFormat::Json => Self::generate_json_output(report),
+            OutputFormat::Yaml => Self::generate_yaml_output(report),
+            OutputFormat::Csv => Ok(Self::generate_csv_output(report)),
+            OutputFormat::Markdown => Ok(Self::generate_markdown_output(report)),
+            OutputFormat::Plain => Ok(Self::generate_plain_output(report)),
+        }
+    }
+
+    /// Format batch conversion results in the specified format
+    pub fn format_batch_output(format: &BatchFormat, records: &[BatchRecord]) -> ConversionResult<String> {
+        match format {
+            BatchFormat::Jsonl => Self::generate_batch_jsonl(records),
+            BatchFormat::Csv => Ok(Self::generate_batch_csv(records)),
+        }
+    }
+
+    /// Generate JSON Lines output with one object per query
+    fn generate_batch_jsonl(records: &[BatchRecord]) -> ConversionResult<String> {
+        let mut output = String::new();
+
+        for record in records {
+            let line = serde_json::to_string(record)
+                .map_err(|e| ConversionError::SerializationError(format!("Failed to serialize JSON output: {}", e)))?;
+            output.push_str(&line);
+            output.push('\n');
+        }
+
+        Ok(output)
+    }
+
+    /// Generate CSV output with one row per query and target platform
+    fn generate_batch_csv(records: &[BatchRecord]) -> String {
+        let mut output = String::from("id,source_platform,original_query,target_platform,converted_query,warnings,error\n");
+
+        for record in records {
+            let error = record.error.as_deref().unwrap_or("");
+            let source_platform = record.source_platform.as_deref().unwrap_or("");
+            // Warnings about the original query are repeated on every row
+            let mut rows: Vec<[String; 3]> = record
+                .conversions
+                .iter()
+                .map(|conversion| {
+                    let warnings: Vec<String> = record.warnings.iter().chain(&conversion.warnings).cloned().collect();
+                    [conversion.platform.clone(), conversion.query.clone(), warnings.join("; ")]
+                })
+                .collect();
+
+            // Failed queries have no conversions but still get a row carrying the error
+            if rows.is_empty() {
+                rows.push([String::new(), String::new(), record.warnings.join("; ")]);
+            }
+
+            for [platform, query, warnings] in rows {
+                let row = [&record.id, source_platform, &record.original_query, &platform, &query, &warnings, error];
+                let cells: Vec<String> = row.iter().map(|cell| csv_escape(cell)).collect();
+                output.push_str(&cells.join(","));
+                output.push('\n');
+            }
+        }
+
+        output
+    }
+
+    /// Format the field mapping matrix in the specified format
+    pub fn format_field_matrix(format: &MatrixFormat, matrix: &FieldMatrix) -> ConversionResult<String> {
         match format {
-            OutputFormat::Raw => Self::generate_raw_output(source_platform, query, conversions),
-            OutputFormat::Json => Self::generate_json_output(source_platform, query, conversions),
+            MatrixFormat::Table => Ok(Self::generate_matrix_table(matrix)),
+            MatrixFormat::Markdown => Ok(Self::generate_matrix_markdown(matrix)),
+            MatrixFormat::Csv => Ok(Self::generate_matrix_csv(matrix)),
+            MatrixFormat::Json => serde_json::to_string_pretty(matrix)
+                .map(|json| json + "\n")
+                .map_err(|e| ConversionError::SerializationError(format!("Failed to serialize JSON output: {}", e))),
         }
     }
+
+    /// Format the result of `config check`
+    pub fn format_config_check(
+        format: &ConfigOutputFormat,
+        config_manager: &ConfigManager,
+        issues: &[ConfigIssue],
+    ) -> ConversionResult<String> {
+        let platforms = config_manager.get_supported_platforms();
+        let valid = !issues.iter().any(|issue| issue.severity == IssueSeverity::Error);
+
+        match format {
+            ConfigOutputFormat::Text => {
+                let mut output = String::new();
+                for issue in issues {
+                    output.push_str(&format!("{}\n", issue));
+                }
+                if valid {
+                    output.push_str(&format!(
+                        "Configuration is valid: {} platforms ({})\n",
+                        platforms.len(),
+                        platforms.join(", ")
+                    ));
+                }
+                output.push_str(&format!("Loaded from: {}\n", config_manager.sources().join(", ")));
+                Ok(output)
+            }
+            ConfigOutputFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+                "valid": valid,
+                "platforms": platforms,
+                "sources": config_manager.sources(),
+                "issues": issues,
+            }))
+            .map(|json| json + "\n")
+            .map_err(|e| ConversionError::SerializationError(format!("Failed to serialize JSON output: {}", e))),
+        }
+    }
+
+    /// Format the configuration layers, or the merged configuration with the source of each value
+    pub fn format_resolved_config(
+        format: &ConfigOutputFormat,
+        resolved: &ResolvedConfig,
+        show_values: bool,
+    ) -> ConversionResult<String> {
+        match format {
+            ConfigOutputFormat::Text if show_values => {
+                let mut leaves = Vec::new();
+                Self::collect_config_leaves(&resolved.config, "", &mut leaves);
+
+                let mut output = String::new();
+                for (path, value) in leaves {
+                    let source = resolved.origins.get(&path).map(String::as_str).unwrap_or("-");
+                    output.push_str(&format!("{} = {}  # {}\n", path, value, source));
+                }
+                Ok(output)
+            }
+            ConfigOutputFormat::Text => Ok(resolved
+                .sources
+                .iter()
+                .enumerate()
+                .map(|(i, source)| format!("{}. {}\n", i + 1, source))
+                .collect()),
+            ConfigOutputFormat::Json => {
+                let json = if show_values {
+                    serde_json::to_string_pretty(resolved)
+                } else {
+                    serde_json::to_string_pretty(&serde_json::json!({ "sources": resolved.sources }))
+                };
+                json.map(|json| json + "\n")
+                    .map_err(|e| ConversionError::SerializationError(format!("Failed to serialize JSON output: {}", e)))
+            }
+        }
+    }
+
+    /// Collect the leaf values of a configuration with their `/`-separated paths
+    fn collect_config_leaves(value: &Value, path: &str, leaves: &mut Vec<(String, Value)>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, child) in map {
+                    let child_path = if path.is_empty() { key.clone() } else { format!("{}/{}", path, key) };
+                    Self::collect_config_leaves(child, &child_path, leaves);
+                }
+            }
+            _ => leaves.push((path.to_string(), value.clone())),
+        }
+    }
+
+    /// Format field metadata for `fields --describe`
+    pub fn format_field_catalog(format: &MatrixFormat, catalog: &FieldCatalog) -> ConversionResult<String> {
+        match format {
+            MatrixFormat::Table => Ok(Self::generate_catalog_text(catalog)),
+            MatrixFormat::Markdown => {
+                let rows = Self::catalog_cells(catalog);
+                let mut output = String::new();
+                for (i, row) in rows.iter().enumerate() {
+                    let cells: Vec<String> = row.iter().map(|cell| markdown_escape(cell)).collect();
+                    output.push_str(&format!("| {} |\n", cells.join(" | ")));
+                    if i == 0 {
+                        output.push_str(&format!("|{}\n", " --- |".repeat(row.len())));
+                    }
+                }
+                Ok(output)
+            }
+            MatrixFormat::Csv => Ok(Self::catalog_cells(catalog)
+                .iter()
+                .map(|row| row.iter().map(|cell| csv_escape(cell)).collect::<Vec<_>>().join(",") + "\n")
+                .collect()),
+            MatrixFormat::Json => serde_json::to_string_pretty(catalog)
+                .map(|json| json + "\n")
+                .map_err(|e| ConversionError::SerializationError(format!("Failed to serialize JSON output: {}", e))),
+        }
+    }
+
+    /// Describe each field with one indented block per platform
+    fn generate_catalog_text(catalog: &FieldCatalog) -> String {
+        let mut output = String::new();
+
+        for (i, entry) in catalog.fields.iter().enumerate() {
+            if i > 0 {
+                output.push('\n');
+            }
+            output.push_str(&format!("{}\n", entry.field));
+
+            for platform in &catalog.platforms {
+                let Some(spec) = entry.platforms.get(platform) else {
+                    output.push_str(&format!("  {}: {}\n", platform, MATRIX_GAP_MARKER));
+                    continue;
+                };
+
+                let mut traits = vec![spec.field_type.to_string()];
+                if !spec.match_modes.is_empty() {
+                    let modes: Vec<String> = spec.match_modes.iter().map(ToString::to_string).collect();
+                    traits.push(format!("match: {}", modes.join("/")));
+                }
+                if spec.vip {
+                    traits.push("VIP".to_string());
+                }
+                if spec.platform_only {
+                    traits.push("platform-only".to_string());
+                }
+                if spec.broad {
+                    traits.push("broad".to_string());
+                }
+                if spec.case_sensitive {
+                    traits.push("case-sensitive".to_string());
+                }
+                if let Some(policy) = spec.on_missing {
+                    traits.push(format!("on missing: {}", policy));
+                }
+                output.push_str(&format!("  {}: {} ({})\n", platform, spec.name, traits.join(", ")));
+
+                if let Some(description) = &spec.description {
+                    output.push_str(&format!("    {}\n", description));
+                }
+                if !spec.aliases.is_empty() {
+                    output.push_str(&format!("    aliases: {}\n", spec.aliases.join(", ")));
+                }
+                if !spec.examples.is_empty() {
+                    output.push_str(&format!("    examples: {}\n", spec.examples.join(", ")));
+                }
+                for (target, approximation) in &spec.approximations {
+                    let value = approximation.value.as_deref().map(|value| format!(" = {}", value)).unwrap_or_default();
+                    output.push_str(&format!("    on {}: approximated with {}{}\n", target, approximation.field, value));
+                }
+            }
+        }
+
+        output
+    }
+
+    /// Collect one row per field and platform, including the header row
+    fn catalog_cells(catalog: &FieldCatalog) -> Vec<Vec<String>> {
+        let mut rows = vec![["field", "platform", "name", "type", "match_modes", "vip", "aliases", "description", "examples"]
+            .iter()
+            .map(ToString::to_string)
+            .collect()];
+
+        for entry in &catalog.fields {
+            for (platform, spec) in &entry.platforms {
+                let modes: Vec<String> = spec.match_modes.iter().map(ToString::to_string).collect();
+                rows.push(vec![
+                    entry.field.clone(),
+                    platform.clone(),
+                    spec.name.clone(),
+                    spec.field_type.to_string(),
+                    modes.join(" "),
+                    spec.vip.to_string(),
+                    spec.aliases.join(" "),
+                    spec.description.clone().unwrap_or_default(),
+                    spec.examples.join("; "),
+                ]);
+            }
+        }
+
+        rows
+    }
+
+    /// Collect the matrix cells row by row, including the header row
+    fn matrix_cells(matrix: &FieldMatrix) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+
+        let mut header = vec!["field".to_string()];
+        header.extend(matrix.platforms.iter().cloned());
+        rows.push(header);
+
+        for row in &matrix.fields {
+            let mut cells = vec![row.field.clone()];
+            for platform in &matrix.platforms {
+                let native_name = row.platforms.get(platform).cloned().flatten();
+                cells.push(native_name.unwrap_or_else(|| MATRIX_GAP_MARKER.to_string()));
+            }
+            rows.push(cells);
+        }
+
+        rows
+    }
+
+    /// Generate an aligned text table of the matrix
+    fn generate_matrix_table(matrix: &FieldMatrix) -> String {
+        let rows = Self::matrix_cells(matrix);
+        let mut widths = vec![0; rows[0].len()];
+        for row in &rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+
+        let mut output = String::new();
+        for row in rows {
+            let line: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| format!("{:width$}", cell, width = widths[i]))
+                .collect();
+            output.push_str(line.join("  ").trim_end());
+            output.push('\n');
+        }
+
+        output
+    }
+
+    /// Generate a Markdown table of the matrix
+    fn generate_matrix_markdown(matrix: &FieldMatrix) -> String {
+        let rows = Self::matrix_cells(matrix);
+        let mut output = String::new();
+
+        for (i, row) in rows.iter().enumerate() {
+            let cells: Vec<String> = if i == 0 {
+                row.clone()
+            } else {
+                // Render names as code so dotted names stay readable, but keep gaps plain
+                row.iter()
+                    .map(|cell| if cell == MATRIX_GAP_MARKER { cell.clone() } else { markdown_code(cell) })
+                    .collect()
+            };
+            output.push_str(&format!("| {} |\n", cells.join(" | ")));
+            if i == 0 {
+                output.push_str(&format!("|{}\n", " --- |".repeat(row.len())));
+            }
+        }
+
+        output
+    }
+
+    /// Generate CSV output of the matrix, leaving gaps empty
+    fn generate_matrix_csv(matrix: &FieldMatrix) -> String {
+        let rows = Self::matrix_cells(matrix);
+        let mut output = String::new();
+
+        for row in rows {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|cell| if cell == MATRIX_GAP_MARKER { String::new() } else { csv_escape(cell) })
+                .collect();
+            output.push_str(&cells.join(","));
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+/// Marker shown where a platform has no equivalent field
+const MATRIX_GAP_MARKER: &str = "-";
+
+/// Quote a CSV cell when it contains separators, quotes or line breaks
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Escape pipes so a value stays within its Markdown table cell
+fn markdown_escape(cell: &str) -> String {
+    cell.replace('|', "\\|")
+}
+
+/// Write text as a Markdown code span, fenced by more backticks than it contains in a row
+fn markdown_code(text: &str) -> String {
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run + 1);
+    // A space keeps a backtick at either end from joining the fence
+    let padding = if text.starts_with('`') || text.ends_with('`') { " " } else { "" };
+    format!("{}{}{}{}{}", fence, padding, text, padding, fence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FieldMatrixRow;
+    use crate::converter::{EncodedQuery, PlatformConversion};
+    use std::collections::BTreeMap;
+
+    fn conversion(platform: &str, query: &str, warnings: &[&str]) -> PlatformConversion {
+        PlatformConversion {
+            platform: platform.to_string(),
+            query: query.to_string(),
+            mapped_fields: Vec::new(),
+            unmapped_fields: Vec::new(),
+            warnings: warnings.iter().map(|warning| warning.to_string()).collect(),
+            encoded: EncodedQuery {
+                base64: String::new(),
+                url: String::new(),
+                search_url: None,
+            },
+        }
+    }
+
+    fn report(query: &str, conversions: Vec<PlatformConversion>) -> ConversionReport {
+        ConversionReport {
+            source_platform: "fofa".to_string(),
+            original_query: query.to_string(),
+            warnings: Vec::new(),
+            conversions,
+            elapsed_us: 0,
+        }
+    }
+
+    #[test]
+    fn csv_quotes_cells_and_joins_warnings() {
+        let mut report = report(r#"title="a,b""#, vec![conversion("quake", r#"title:"a,b""#, &["Dropped 'x'"])]);
+        report.warnings.push("Broad search".to_string());
+        assert_eq!(
+            OutputFormatter::generate_csv_output(&report),
+            "source_platform,original_query,target_platform,converted_query,warnings,search_url\n\
+             fofa,\"title=\"\"a,b\"\"\",quake,\"title:\"\"a,b\"\"\",Broad search; Dropped 'x',\n"
+        );
+    }
+
+    #[test]
+    fn yaml_holds_the_report() {
+        let report = report(r#"title="a""#, vec![conversion("quake", r#"title:"a""#, &[])]);
+        let yaml = OutputFormatter::generate_yaml_output(&report).unwrap();
+        let parsed: ConversionReport = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed.original_query, report.original_query);
+        assert_eq!(parsed.conversions[0].query, r#"title:"a""#);
+    }
+
+    #[test]
+    fn markdown_fences_backticks_and_escapes_pipes() {
+        let report = report(r#"title="a`b""#, vec![conversion("quake", r#"title:"a`b" OR body:"x|y""#, &[])]);
+        assert_eq!(
+            OutputFormatter::generate_markdown_output(&report),
+            "**Source platform:** fofa\n\n\
+             **Original query:** ``title=\"a`b\"``\n\n\
+             | Platform | Query |\n| --- | --- |\n\
+             | quake | ``title:\"a`b\" OR body:\"x\\|y\"`` |\n"
+        );
+        assert_eq!(markdown_code("a"), "`a`");
+        assert_eq!(markdown_code("``a``"), "``` ``a`` ```");
+    }
+
+    #[test]
+    fn plain_output_lists_platforms_only_for_several_targets() {
+        let single = report("a", vec![conversion("quake", "b", &[])]);
+        assert_eq!(OutputFormatter::generate_plain_output(&single), "b\n");
+        let several = report("a", vec![conversion("quake", "b", &[]), conversion("hunter", "c", &[])]);
+        assert_eq!(OutputFormatter::generate_plain_output(&several), "quake\tb\nhunter\tc\n");
+    }
+
+    #[test]
+    fn output_files_hold_only_the_content() {
+        let path = std::env::temp_dir().join(format!("convertix-output-{}.txt", std::process::id()));
+        OutputFormatter::write_output("title=\"a\"\n".to_string(), Some(path.clone())).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, "title=\"a\"\n");
+    }
+
+    #[test]
+    fn matrix_formats_mark_gaps() {
+        let matrix = FieldMatrix {
+            platforms: vec!["fofa".to_string(), "hunter".to_string()],
+            fields: vec![FieldMatrixRow {
+                field: "title".to_string(),
+                platforms: BTreeMap::from([
+                    ("fofa".to_string(), Some("title".to_string())),
+                    ("hunter".to_string(), None),
+                ]),
+            }],
+        };
+        assert_eq!(
+            OutputFormatter::format_field_matrix(&MatrixFormat::Markdown, &matrix).unwrap(),
+            "| field | fofa | hunter |\n| --- | --- | --- |\n| `title` | `title` | - |\n"
+        );
+        assert_eq!(
+            OutputFormatter::format_field_matrix(&MatrixFormat::Csv, &matrix).unwrap(),
+            "field,fofa,hunter\ntitle,title,\n"
+        );
+        let table = OutputFormatter::format_field_matrix(&MatrixFormat::Table, &matrix).unwrap();
+        assert!(table.lines().nth(1).is_some_and(|line| line.contains("title") && line.trim_end().ends_with('-')), "{}", table);
+    }
 }